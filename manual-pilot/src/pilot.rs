@@ -39,7 +39,39 @@ impl Pilot for PilotState {
                     thrusters: true,
                     ..cmd
                 },
+                Keycode::Down => rpc::Command {
+                    reverse_thrust: Some(1.0),
+                    ..cmd
+                },
+                Keycode::Z => rpc::Command {
+                    strafe: Some(-1.0),
+                    ..cmd
+                },
+                Keycode::X => rpc::Command {
+                    strafe: Some(1.0),
+                    ..cmd
+                },
                 Keycode::Space => rpc::Command { fire: true, ..cmd },
+                Keycode::Key1 => rpc::Command {
+                    weapon: Some(rpc::Weapon::Gun as i32),
+                    ..cmd
+                },
+                Keycode::Key2 => rpc::Command {
+                    weapon: Some(rpc::Weapon::Spread as i32),
+                    ..cmd
+                },
+                Keycode::Key3 => rpc::Command {
+                    weapon: Some(rpc::Weapon::Laser as i32),
+                    ..cmd
+                },
+                Keycode::Key4 => rpc::Command {
+                    weapon: Some(rpc::Weapon::Mine as i32),
+                    ..cmd
+                },
+                Keycode::Key5 => rpc::Command {
+                    weapon: Some(rpc::Weapon::Homing as i32),
+                    ..cmd
+                },
                 Keycode::S => {
                     let game_state = request.get_ref();
                     match &game_state.ship {
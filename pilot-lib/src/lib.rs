@@ -1,3 +1,4 @@
 pub mod steering;
 pub mod pilot_base;
+pub mod targeting;
 pub mod velocity_model;
@@ -1,24 +1,145 @@
 //! Boilerplate for common pilot implementations
 use roid_rage_grpc::roid_rage as rpc;
 use roid_rage_grpc::roid_rage::pilot_server::{Pilot, PilotServer};
+use roid_rage_grpc::roid_rage::{game_message, pilot_message};
+use roid_rage_grpc::roid_rage::pilot_stream_server::{PilotStream, PilotStreamServer};
+use std::pin::Pin;
+use std::sync::Arc;
 use structopt::StructOpt;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
 use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "basic")]
 struct Opt {
-	/// The address on which the pilot will listen
-	pilot_address: String,
+	/// The address on which the pilot will listen. Not needed with --connect.
+	#[structopt(required_unless = "connect")]
+	pilot_address: Option<String>,
+
+	/// Connect to the game instead of waiting for it to connect to us. Use this when the game
+	/// can't reach the pilot, e.g. from behind NAT.
+	#[structopt(long)]
+	connect: bool,
 
 	// An argument of type float, with a default value.
 	#[structopt(short, long, default_value = "[::1]:50051", name = "address")]
 	server: String,
+
+	/// Name to show in the game instead of the pilot's address
+	#[structopt(long, default_value = "")]
+	name: String,
+
+	/// Team to play for
+	#[structopt(long, default_value = "")]
+	team: String,
+
+	/// Colour for the pilot's ship as RGB hex, e.g. "ff8000"
+	#[structopt(long, parse(try_from_str = parse_color))]
+	color: Option<rpc::Color>,
+}
+
+/// Parse an RGB hex colour like "ff8000" or "#ff8000".
+fn parse_color(s: &str) -> Result<rpc::Color, String> {
+	let hex = s.trim_start_matches('#');
+	let value = u32::from_str_radix(hex, 16)
+		.ok()
+		.filter(|_| hex.len() == 6)
+		.ok_or_else(|| format!("invalid colour '{}', expected RGB hex like ff8000", s))?;
+	let component = |shift: u32| ((value >> shift) & 0xff) as f32 / 255.0;
+	Ok(rpc::Color {
+		r: component(16),
+		g: component(8),
+		b: component(0),
+	})
+}
+
+/// Adapts a request/response `Pilot` to the streaming `PilotStream` service.
+///
+/// Incoming game states are collapsed so that the pilot is always asked about the newest one;
+/// if it is slower than the game, the states it didn't get to are skipped.
+pub struct StreamingPilot<P> {
+	pilot: Arc<P>,
+}
+
+impl<P> StreamingPilot<P> {
+	pub fn new(pilot: Arc<P>) -> StreamingPilot<P> {
+		StreamingPilot { pilot }
+	}
+}
+
+#[tonic::async_trait]
+impl<P> PilotStream for StreamingPilot<P>
+where
+	P: Pilot,
+{
+	type PlayStream = Pin<Box<dyn Stream<Item = Result<rpc::Command, Status>> + Send>>;
+
+	async fn play(
+		&self,
+		request: Request<Streaming<rpc::GameState>>,
+	) -> Result<Response<Self::PlayStream>, Status> {
+		let mut inbound = request.into_inner();
+		let (state_tx, state_rx) = watch::channel(None);
+
+		// Keep only the latest game state from the game.
+		tokio::spawn(async move {
+			while let Ok(Some(state)) = inbound.message().await {
+				if state_tx.send(Some(state)).is_err() {
+					break;
+				}
+			}
+		});
+
+		let command_rx = answer_states(self.pilot.clone(), state_rx);
+		Ok(Response::new(Box::pin(ReceiverStream::new(command_rx).map(Ok::<_, Status>))))
+	}
+}
+
+/// Ask `pilot` about each new game state, returning its commands.
+///
+/// If the pilot fails to answer a state, the error is logged and that state is skipped. The game's
+/// deadline and fallback command cover the missed tick, and the pilot stays in the game.
+fn answer_states<P>(
+	pilot: Arc<P>,
+	mut states: watch::Receiver<Option<rpc::GameState>>,
+) -> mpsc::Receiver<rpc::Command>
+where
+	P: Pilot,
+{
+	let (command_tx, command_rx) = mpsc::channel(1);
+
+	tokio::spawn(async move {
+		while states.changed().await.is_ok() {
+			let state = match states.borrow_and_update().clone() {
+				Some(state) => state,
+				None => continue,
+			};
+
+			let command = match pilot.get_command(Request::new(state)).await {
+				Ok(response) => response.into_inner(),
+				Err(status) => {
+					println!("Error getting command from pilot: {}", status);
+					continue;
+				}
+			};
+
+			if command_tx.send(command).await.is_err() {
+				break;
+			}
+		}
+	});
+
+	command_rx
 }
 
 /// Implementation of main() for Pilots.
 ///
 /// This main implementation will give you a program that accepts
-/// arguments for the pilot address and the game address.
+/// arguments for the pilot address and the game address. Interrupting
+/// it with Ctrl-C unregisters the pilot from the game before exiting.
 /// 
 /// # Example
 /// 
@@ -47,27 +168,211 @@ where
 	P: Pilot,
 {
 	let opt = Opt::from_args();
+	let pilot = Arc::new(pilot);
+	let server_address = format!("http://{}", opt.server);
+	let registration = rpc::RegistrationRequest {
+		url: String::new(),
+		name: opt.name,
+		team: opt.team,
+		protocol_version: rpc::PROTOCOL_VERSION,
+		color: opt.color,
+	};
+
+	match opt.pilot_address {
+		Some(pilot_address) if !opt.connect => {
+			serve(pilot, &pilot_address, server_address, registration).await
+		}
+		_ => join(pilot, server_address, registration).await,
+	}
+}
 
-	let pilot_address = opt.pilot_address;
+/// Listen for the game on `pilot_address` after registering with it.
+async fn serve<P>(
+	pilot: Arc<P>,
+	pilot_address: &str,
+	server_address: String,
+	mut registration: rpc::RegistrationRequest,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+	P: Pilot,
+{
 	let pilot_url = format!("http://{}", pilot_address);
 
-	let svc = PilotServer::new(pilot);
+	let svc = PilotServer::from_arc(pilot.clone());
+	let stream_svc = PilotStreamServer::new(StreamingPilot::new(pilot));
 
 	// Run the pilot service
 	let handle = tokio::spawn(
 		Server::builder()
 			.add_service(svc)
-			.serve(pilot_address.parse().unwrap()),
+			.add_service(stream_svc)
+			.serve(pilot_address.parse()?),
 	);
 
-	let server_address = format!("http://{}", opt.server);
-
 	// Register with game
 	let mut client =
 		rpc::pilot_registrar_client::PilotRegistrarClient::connect(server_address).await?;
-	let request = rpc::RegistrationRequest { url: pilot_url };
-	client.register(request).await?;
+	registration.url = pilot_url.clone();
+	let response = client.register(registration).await?.into_inner();
+	println!("Registered as pilot {}", response.pilot_id);
 
-	let _result = handle.await?;
+	// Serve until interrupted, then leave the game rather than just disappearing.
+	tokio::select! {
+		result = handle => {
+			let _result = result?;
+		}
+		_ = tokio::signal::ctrl_c() => {
+			let request = rpc::UnregistrationRequest {
+				url: pilot_url,
+				pilot_id: response.pilot_id,
+			};
+			let response = client.unregister(request).await?.into_inner();
+			if let Some(stats) = response.stats {
+				println!(
+					"Left the game with a score of {} after {:.1}s",
+					stats.score, stats.survival_time
+				);
+			}
+		}
+	}
 	Ok(())
 }
+
+/// Open a connection to the game and play over it.
+async fn join<P>(
+	pilot: Arc<P>,
+	server_address: String,
+	registration: rpc::RegistrationRequest,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+	P: Pilot,
+{
+	let mut client =
+		rpc::pilot_registrar_client::PilotRegistrarClient::connect(server_address).await?;
+
+	// Our side of the connection: the registration followed by commands
+	let (outbound_tx, outbound_rx) = mpsc::channel(1);
+	outbound_tx
+		.send(rpc::PilotMessage {
+			message: Some(pilot_message::Message::Register(registration)),
+		})
+		.await?;
+	let mut inbound = client
+		.join(ReceiverStream::new(outbound_rx))
+		.await?
+		.into_inner();
+
+	match inbound.message().await? {
+		Some(rpc::GameMessage {
+			message: Some(game_message::Message::Registered(response)),
+		}) => println!("Registered as pilot {}", response.pilot_id),
+		_ => return Err("the game didn't accept the registration".into()),
+	}
+
+	let (state_tx, state_rx) = watch::channel(None);
+	tokio::spawn(forward_commands(pilot, state_rx, outbound_tx));
+
+	// Play until the game goes away or we're interrupted. Dropping the connection is how we
+	// leave the game.
+	let play = async {
+		while let Some(message) = inbound.message().await? {
+			if let Some(game_message::Message::State(state)) = message.message {
+				state_tx.send_replace(Some(*state));
+			}
+		}
+		Ok::<(), Status>(())
+	};
+	tokio::select! {
+		result = play => result?,
+		_ = tokio::signal::ctrl_c() => println!("Left the game"),
+	}
+	Ok(())
+}
+
+/// Send `pilot`'s answer to each new game state to the game as a command message.
+///
+/// This runs for as long as the connection does; a state the pilot fails to answer doesn't stop
+/// later commands from being sent.
+async fn forward_commands<P>(
+	pilot: Arc<P>,
+	states: watch::Receiver<Option<rpc::GameState>>,
+	outbound: mpsc::Sender<rpc::PilotMessage>,
+) where
+	P: Pilot,
+{
+	let mut commands = answer_states(pilot, states);
+	while let Some(command) = commands.recv().await {
+		let message = rpc::PilotMessage {
+			message: Some(pilot_message::Message::Command(command)),
+		};
+		if outbound.send(message).await.is_err() {
+			break;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_hex_colors() {
+		let color = parse_color("#ff8000").unwrap();
+		assert_eq!(color.r, 1.0);
+		assert_eq!(color.g, 128.0 / 255.0);
+		assert_eq!(color.b, 0.0);
+		assert_eq!(parse_color("00ff00").unwrap().g, 1.0);
+	}
+
+	#[test]
+	fn rejects_bad_colors() {
+		assert!(parse_color("orange").is_err());
+		assert!(parse_color("fff").is_err());
+	}
+
+	/// Fails to answer the first game state, then fires at every one after that.
+	struct FailsOnce {
+		failed: std::sync::atomic::AtomicBool,
+	}
+
+	#[tonic::async_trait]
+	impl Pilot for FailsOnce {
+		async fn get_command(
+			&self,
+			_request: Request<rpc::GameState>,
+		) -> Result<Response<rpc::Command>, Status> {
+			if !self.failed.swap(true, std::sync::atomic::Ordering::SeqCst) {
+				return Err(Status::internal("pilot failure"));
+			}
+			Ok(Response::new(rpc::Command {
+				fire: true,
+				..rpc::Command::null()
+			}))
+		}
+	}
+
+	#[tokio::test]
+	async fn keeps_forwarding_commands_after_a_pilot_error() {
+		let pilot = Arc::new(FailsOnce {
+			failed: std::sync::atomic::AtomicBool::new(false),
+		});
+		let (state_tx, state_rx) = watch::channel(None);
+		let (outbound_tx, mut outbound_rx) = mpsc::channel(1);
+		tokio::spawn(forward_commands(pilot.clone(), state_rx, outbound_tx));
+
+		// The first state fails...
+		state_tx.send_replace(Some(rpc::GameState::default()));
+		while !pilot.failed.load(std::sync::atomic::Ordering::SeqCst) {
+			tokio::task::yield_now().await;
+		}
+
+		// ...but the next one is still answered and sent to the game.
+		state_tx.send_replace(Some(rpc::GameState::default()));
+		match outbound_rx.recv().await {
+			Some(rpc::PilotMessage {
+				message: Some(pilot_message::Message::Command(command)),
+			}) => assert!(command.fire),
+			other => panic!("expected a command, got {:?}", other),
+		}
+	}
+}
@@ -53,6 +53,7 @@ mod tests {
             thrust: 1.0,
             velocity: Some(rpc::Velocity { x: 0.0, y: 0.0 }),
             cannon: None,
+            ..Default::default()
         }
     }
 
@@ -61,6 +62,7 @@ mod tests {
             radius: 1.0,
             position: Some(rpc::Position { x, y }),
             velocity: None,
+            ..Default::default()
         }
     }
 
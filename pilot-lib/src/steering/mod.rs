@@ -19,3 +19,14 @@ fn turn_or_thrust(heading: f32, target: Vec2) -> rpc::Command {
     }
     cmd
 }
+
+fn turn_or_reverse(heading: f32, target: Vec2) -> rpc::Command {
+    let mut cmd = rpc::Command::null();
+    let rotation = turn_to(heading, target);
+    if rotation != rpc::Rotation::None {
+        cmd.rotation = rotation as i32;
+    } else {
+        cmd.reverse_thrust = Some(1.0);
+    }
+    cmd
+}
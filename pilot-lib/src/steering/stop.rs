@@ -1,13 +1,15 @@
 use roid_rage_grpc::roid_rage as rpc;
-use sted::Velocity;
+use sted::{to_vector, Velocity};
 
-use super::turn_or_thrust;
+use super::{turn_or_reverse, turn_or_thrust};
 
 /// Bring the ship to a stop
 ///
 /// Rotate until we're facing the opposite direction of the
 /// ship's heading, and then fire thrusters until speed
-/// is zero.
+/// is zero. Ships with retro thrusters which are already
+/// facing the way they're moving turn the rest of the way
+/// towards their velocity and fire those instead.
 pub fn stop(ship: &rpc::Ship) -> rpc::Command {
     let velocity = ship.velocity();
 
@@ -16,7 +18,11 @@ pub fn stop(ship: &rpc::Ship) -> rpc::Command {
         return rpc::Command::null();
     }
 
-    turn_or_thrust(ship.heading, -velocity)
+    if ship.reverse_thrust > 0.0 && to_vector(ship.heading).dot(velocity) > 0.0 {
+        turn_or_reverse(ship.heading, velocity)
+    } else {
+        turn_or_thrust(ship.heading, -velocity)
+    }
 }
 
 #[cfg(test)]
@@ -37,6 +43,7 @@ mod tests {
                 // moving 'south'
                 velocity: Some(rpc::Velocity { x: 0.0, y: 1.0 }),
                 cannon: None,
+                ..Default::default()
             };
             let cmd = stop(&ship);
 
@@ -56,6 +63,7 @@ mod tests {
                 // moving 'north'
                 velocity: Some(rpc::Velocity { x: 0.0, y: -1.0 }),
                 cannon: None,
+                ..Default::default()
             };
             let cmd = stop(&ship);
 
@@ -75,6 +83,7 @@ mod tests {
                 // moving 'west'
                 velocity: Some(rpc::Velocity { x: -1.0, y: 0.0 }),
                 cannon: None,
+                ..Default::default()
             };
             let cmd = stop(&ship);
 
@@ -94,6 +103,7 @@ mod tests {
                 // moving 'west'
                 velocity: Some(rpc::Velocity { x: 0.0, y: 0.0 }),
                 cannon: None,
+                ..Default::default()
             };
             let cmd = stop(&ship);
 
@@ -101,5 +111,42 @@ mod tests {
             assert!(!cmd.thrusters);
             assert!(!cmd.fire);
         }
+
+        #[test]
+        fn test_fire_retro_thrusters_when_facing_forwards() {
+            let ship = rpc::Ship {
+                // facing 'east'
+                heading: 0.0,
+                mass: 1.0,
+                thrust: 1.0,
+                reverse_thrust: 0.5,
+                // moving 'east'
+                velocity: Some(rpc::Velocity { x: 1.0, y: 0.0 }),
+                ..Default::default()
+            };
+            let cmd = stop(&ship);
+
+            assert_eq!(cmd.rotation, rpc::Rotation::None as i32);
+            assert_eq!(cmd.reverse_thrust, Some(1.0));
+            assert!(!cmd.thrusters);
+        }
+
+        #[test]
+        fn test_turn_towards_velocity_to_use_retro_thrusters() {
+            let ship = rpc::Ship {
+                // facing 'east'
+                heading: 0.0,
+                mass: 1.0,
+                thrust: 1.0,
+                reverse_thrust: 0.5,
+                // moving 'south-east'
+                velocity: Some(rpc::Velocity { x: 1.0, y: 1.0 }),
+                ..Default::default()
+            };
+            let cmd = stop(&ship);
+
+            assert_eq!(cmd.rotation, rpc::Rotation::Clockwise as i32);
+            assert_eq!(cmd.reverse_thrust, None);
+        }
     }
 }
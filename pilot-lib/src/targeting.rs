@@ -0,0 +1,358 @@
+//! Choosing what to shoot at.
+//!
+//! `intercepts` works out everywhere a bullet fired now could hit a roid, and a `Strategy`
+//! picks which of those to go for.
+use glam::Vec2;
+use nalgebra::Vector2;
+use roid_rage_grpc::roid_rage as rpc;
+use std::cmp::Ordering;
+use sted::collide::collision_point;
+use sted::Field;
+
+/// A way of hitting a roid with a bullet fired now.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Intercept {
+    /// Id of the roid.
+    pub roid: u64,
+    /// Bearing to fire along, in radians.
+    pub bearing: f32,
+    /// Seconds from firing until the bullet hits.
+    pub time: f32,
+    /// Where the bullet hits.
+    pub position: Vec2,
+    /// Where the roid is now. For a roid that's hit after wrapping around the field this is
+    /// the position of the wrapped image, i.e. off the field.
+    pub roid_position: Vec2,
+    pub roid_velocity: Vec2,
+    pub roid_radius: f32,
+}
+
+/// Every possible intercept of a roid in the game.
+///
+/// Roids wrap around the edges of the field, so as well as each roid itself we consider its
+/// images one field-width/height away in every direction: a roid about to leave on the right
+/// can be hit as it comes back in on the left. Bullets don't wrap, so only intercepts inside
+/// the field count.
+pub fn intercepts(game_state: &rpc::GameState) -> Vec<Intercept> {
+    let (ship, field) = match (&game_state.ship, &game_state.field) {
+        (Some(ship), Some(field)) => (ship, field),
+        _ => return Vec::new(),
+    };
+    let bullet_speed = match ship.cannon {
+        Some(cannon) if cannon.bullet_speed > 0.0 => cannon.bullet_speed,
+        _ => return Vec::new(),
+    };
+    let firing_position = firing_position(game_state);
+    let field = Field::new(field.width as f32, field.height as f32);
+    let field = &field;
+    let launch = Vector2::new(firing_position.x, firing_position.y);
+
+    game_state
+        .roids
+        .iter()
+        .flat_map(|roid| {
+            let velocity = roid.velocity();
+            let position = roid.position();
+            field
+                .images(position.x, position.y)
+                .into_iter()
+                .map(|(x, y)| Vec2::new(x, y))
+                .filter_map(move |position| {
+                    collision_point(
+                        &launch,
+                        bullet_speed,
+                        &Vector2::new(position.x, position.y),
+                        &Vector2::new(velocity.x, velocity.y),
+                    )
+                    .map(|p| Vec2::new(p[0], p[1]))
+                    .filter(|p| field.contains(p.x, p.y))
+                    .map(|p| {
+                        let shot = p - firing_position;
+                        Intercept {
+                            roid: roid.id,
+                            bearing: shot.y.atan2(shot.x),
+                            time: shot.length() / bullet_speed,
+                            position: p,
+                            roid_position: position,
+                            roid_velocity: velocity,
+                            roid_radius: roid.radius,
+                        }
+                    })
+                })
+        })
+        .collect()
+}
+
+/// Pick a roid to shoot at using `strategy`.
+pub fn find_target<S: Strategy + ?Sized>(
+    game_state: &rpc::GameState,
+    strategy: &S,
+) -> Option<Intercept> {
+    let intercepts = intercepts(game_state);
+    strategy.select(game_state, &intercepts).cloned()
+}
+
+/// A way of choosing which intercept to go for.
+pub trait Strategy {
+    fn select<'a>(
+        &self,
+        game_state: &rpc::GameState,
+        intercepts: &'a [Intercept],
+    ) -> Option<&'a Intercept>;
+}
+
+/// Go for the roid which is closest to the ship right now.
+pub struct Closest;
+
+impl Strategy for Closest {
+    fn select<'a>(
+        &self,
+        game_state: &rpc::GameState,
+        intercepts: &'a [Intercept],
+    ) -> Option<&'a Intercept> {
+        let firing_position = firing_position(game_state);
+        min_by_key(intercepts, |i| (i.roid_position - firing_position).length())
+    }
+}
+
+/// Go for the hit that happens soonest. Bullets fly at a fixed speed, so this is also the hit
+/// closest to the ship.
+pub struct SoonestImpact;
+
+impl Strategy for SoonestImpact {
+    fn select<'a>(
+        &self,
+        _game_state: &rpc::GameState,
+        intercepts: &'a [Intercept],
+    ) -> Option<&'a Intercept> {
+        min_by_key(intercepts, |i| i.time)
+    }
+}
+
+/// Go for the roid worth the most points. Smaller roids are worth more, so this is the
+/// smallest roid, taking the soonest hit if there's a tie.
+pub struct LargestScore;
+
+impl Strategy for LargestScore {
+    fn select<'a>(
+        &self,
+        _game_state: &rpc::GameState,
+        intercepts: &'a [Intercept],
+    ) -> Option<&'a Intercept> {
+        intercepts.iter().min_by(|a, b| {
+            compare(a.roid_radius, b.roid_radius).then_with(|| compare(a.time, b.time))
+        })
+    }
+}
+
+/// Go for the roid most likely to hit the ship.
+///
+/// Roids which will hit the ship if nothing changes come first, soonest collision first.
+/// After those come roids by how close they'll get to the ship.
+pub struct MostThreatening;
+
+impl Strategy for MostThreatening {
+    fn select<'a>(
+        &self,
+        game_state: &rpc::GameState,
+        intercepts: &'a [Intercept],
+    ) -> Option<&'a Intercept> {
+        let ship = game_state.ship.as_ref()?;
+        let (ship_position, ship_velocity) = (ship.position(), ship.velocity());
+
+        intercepts.iter().min_by(|a, b| {
+            let a = threat(a, ship_position, ship_velocity);
+            let b = threat(b, ship_position, ship_velocity);
+            match (a, b) {
+                (Threat::Collision(a), Threat::Collision(b)) => compare(a, b),
+                (Threat::Collision(_), Threat::Miss(_)) => Ordering::Less,
+                (Threat::Miss(_), Threat::Collision(_)) => Ordering::Greater,
+                (Threat::Miss(a), Threat::Miss(b)) => compare(a, b),
+            }
+        })
+    }
+}
+
+enum Threat {
+    /// The roid will hit the ship in this many seconds.
+    Collision(f32),
+    /// The roid will miss the ship by this distance.
+    Miss(f32),
+}
+
+/// How the roid being intercepted will pass the ship if neither changes course.
+fn threat(intercept: &Intercept, ship_position: Vec2, ship_velocity: Vec2) -> Threat {
+    let relative_position = intercept.roid_position - ship_position;
+    let relative_velocity = intercept.roid_velocity - ship_velocity;
+
+    let closest_approach_time = if relative_velocity.length_squared() > 0.0 {
+        (-relative_position.dot(relative_velocity) / relative_velocity.length_squared()).max(0.0)
+    } else {
+        0.0
+    };
+    let miss_distance = (relative_position + relative_velocity * closest_approach_time).length()
+        - intercept.roid_radius;
+
+    if miss_distance <= 0.0 {
+        Threat::Collision(closest_approach_time)
+    } else {
+        Threat::Miss(miss_distance)
+    }
+}
+
+fn firing_position(game_state: &rpc::GameState) -> Vec2 {
+    match (&game_state.firing_position, &game_state.ship) {
+        (Some(position), _) => Vec2::from(position),
+        (None, Some(ship)) => ship.position(),
+        (None, None) => Vec2::ZERO,
+    }
+}
+
+fn min_by_key<F>(intercepts: &[Intercept], key: F) -> Option<&Intercept>
+where
+    F: Fn(&Intercept) -> f32,
+{
+    intercepts.iter().min_by(|a, b| compare(key(a), key(b)))
+}
+
+fn compare(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn roid(id: u64, x: f32, y: f32, dx: f32, dy: f32, radius: f32) -> rpc::Roid {
+        rpc::Roid {
+            id,
+            radius,
+            position: Some(rpc::Position { x, y }),
+            velocity: Some(rpc::Velocity { x: dx, y: dy }),
+            ..Default::default()
+        }
+    }
+
+    fn game_state(x: f32, y: f32, bullet_speed: f32, roids: Vec<rpc::Roid>) -> rpc::GameState {
+        rpc::GameState {
+            field: Some(rpc::Field {
+                width: 800,
+                height: 600,
+            }),
+            firing_position: Some(rpc::Position { x, y }),
+            roids,
+            ship: Some(rpc::Ship {
+                position: Some(rpc::Position { x, y }),
+                cannon: Some(rpc::Cannon {
+                    bullet_speed,
+                    rate_of_fire: 0.5,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_roids_no_target() {
+        let state = game_state(400.0, 300.0, 100.0, vec![]);
+        assert_eq!(find_target(&state, &Closest), None);
+    }
+
+    #[test]
+    fn aims_straight_at_stationary_roid() {
+        let state = game_state(
+            400.0,
+            300.0,
+            100.0,
+            vec![roid(1, 400.0, 400.0, 0.0, 0.0, 10.0)],
+        );
+        let target = find_target(&state, &Closest).unwrap();
+
+        assert_eq!(target.roid, 1);
+        assert!((target.bearing - FRAC_PI_2).abs() < 0.0001);
+        assert!((target.time - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn leads_moving_roid() {
+        // Roid to the east moving north: the shot has to go north of east.
+        let state = game_state(
+            100.0,
+            300.0,
+            100.0,
+            vec![roid(1, 200.0, 300.0, 0.0, 50.0, 10.0)],
+        );
+        let target = find_target(&state, &Closest).unwrap();
+        assert!(target.bearing > 0.0);
+    }
+
+    #[test]
+    fn aims_at_wrap_image() {
+        // The roid is about to leave across the left edge and is moving away from us, so the
+        // only hit is shooting east at it as it comes back in on the right.
+        let state = game_state(
+            700.0,
+            300.0,
+            200.0,
+            vec![roid(1, 5.0, 300.0, -100.0, 0.0, 10.0)],
+        );
+        let target = find_target(&state, &Closest).unwrap();
+
+        assert!(target.bearing.abs() < 0.0001);
+        assert!(target.roid_position.x > 800.0);
+    }
+
+    #[test]
+    fn closest_and_soonest_can_differ() {
+        let state = game_state(
+            400.0,
+            300.0,
+            100.0,
+            vec![
+                // 50 away but fleeing, so it's caught after 2.5s
+                roid(1, 450.0, 300.0, 80.0, 0.0, 10.0),
+                // 150 away and stationary, so it's hit after 1.5s
+                roid(2, 400.0, 150.0, 0.0, 0.0, 10.0),
+            ],
+        );
+
+        assert_eq!(find_target(&state, &Closest).unwrap().roid, 1);
+
+        let soonest = find_target(&state, &SoonestImpact).unwrap();
+        assert_eq!(soonest.roid, 2);
+        assert!((soonest.time - 1.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn largest_score_prefers_small_roids() {
+        let state = game_state(
+            400.0,
+            300.0,
+            100.0,
+            vec![
+                roid(1, 450.0, 300.0, 0.0, 0.0, 40.0),
+                roid(2, 400.0, 100.0, 0.0, 0.0, 10.0),
+            ],
+        );
+        assert_eq!(find_target(&state, &LargestScore).unwrap().roid, 2);
+    }
+
+    #[test]
+    fn most_threatening_prefers_roids_on_collision_course() {
+        let state = game_state(
+            400.0,
+            300.0,
+            100.0,
+            vec![
+                // Close, but moving across and well clear of the ship
+                roid(1, 400.0, 250.0, 50.0, 0.0, 10.0),
+                // Further away but heading straight for the ship
+                roid(2, 200.0, 300.0, 50.0, 0.0, 10.0),
+            ],
+        );
+        assert_eq!(find_target(&state, &MostThreatening).unwrap().roid, 2);
+    }
+}
@@ -13,7 +13,7 @@ enum State {
 }
 
 pub struct VelocityModel {
-    objects: HashMap<u32, State>,
+    objects: HashMap<u64, State>,
 }
 
 impl VelocityModel {
@@ -25,7 +25,7 @@ impl VelocityModel {
 
     pub fn update<'a, I>(&mut self, source: I, time_delta: f32)
     where
-        I: Iterator<Item = (u32, Point2<f32>)>,
+        I: Iterator<Item = (u64, Point2<f32>)>,
     {
         let updates = source.map(|(id, position)| {
             let state = match self.objects.get(&id) {
@@ -56,7 +56,7 @@ impl VelocityModel {
         self.objects = updates.collect();
     }
 
-    pub fn velocity(&self, id: u32) -> Option<Vector2<f32>> {
+    pub fn velocity(&self, id: u64) -> Option<Vector2<f32>> {
         match self.objects.get(&id) {
             Some(State::Tracked(tracked)) => {
                 let dvel = tracked.end - tracked.start;
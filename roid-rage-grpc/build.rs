@@ -1,4 +1,7 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_prost_build::compile_protos("proto/roid-rage/roid-rage.proto")?;
+    tonic_prost_build::configure()
+        // Game states are much bigger than the other messages they share a oneof with
+        .boxed("GameMessage.message.state")
+        .compile_protos(&["proto/roid-rage/roid-rage.proto"], &["proto/roid-rage"])?;
     Ok(())
 }
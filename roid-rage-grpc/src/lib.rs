@@ -1,6 +1,10 @@
 pub mod roid_rage {
     tonic::include_proto!("roid_rage");
 
+    /// Version of the protocol in `roid-rage.proto`. Pilots send this when they register and
+    /// the game turns away any which don't match. Bump it for incompatible changes.
+    pub const PROTOCOL_VERSION: u32 = 1;
+
     /// Allow our grpc Velocity to be used as a sted::Velocity.
     // impl sted::Velocity<f32> for Velocity {
     //     fn dx(&self) -> f32 {
@@ -60,6 +64,11 @@ pub mod roid_rage {
                 fire: false,
                 rotation: Rotation::None as i32,
                 thrusters: false,
+                thrust: None,
+                turn_rate: None,
+                reverse_thrust: None,
+                strafe: None,
+                weapon: None,
             }
         }
     }
@@ -0,0 +1,292 @@
+//! Play autopilots against each other in headless games and report how they did.
+mod pilots;
+mod results;
+
+use pilots::{free_port, wait_for_listener, Entrant, PilotSpec};
+use results::{MatchResult, PilotResult, Tournament};
+use roid_rage::settings::Settings;
+use roid_rage::HeadlessRunner;
+use roid_rage_grpc::roid_rage as rpc;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "roid-rage-tournament")]
+struct Opt {
+    /// Command which starts a pilot, e.g. "target/debug/simple-pilot". A new process is started
+    /// for each match, with the address it should listen on and `--server <game address>`
+    /// appended. May be given more than once.
+    #[structopt(long = "pilot-command")]
+    commands: Vec<String>,
+
+    /// Address of a pilot which is already running. May be given more than once.
+    #[structopt(long = "pilot-address")]
+    addresses: Vec<String>,
+
+    /// Number of pilots in each match. Every combination of this many pilots is played.
+    #[structopt(long, default_value = "2")]
+    pilots_per_match: usize,
+
+    /// Number of matches played by each combination of pilots
+    #[structopt(long, default_value = "3")]
+    matches: u64,
+
+    /// Maximum length of a match in ticks. A match also ends when every pilot is out of lives,
+    /// or when the game mode says it's over.
+    #[structopt(long, default_value = "3600")]
+    ticks: u64,
+
+    /// Seed for the first match of each combination; the n-th match uses seed + n, so every
+    /// combination plays the same games.
+    #[structopt(long, default_value = "1")]
+    seed: u64,
+
+    /// Seconds to wait for all of a match's pilots to register
+    #[structopt(long, default_value = "10")]
+    registration_timeout: u64,
+
+    /// Where to write results; `.json` and `.csv` are added to this.
+    #[structopt(long, default_value = "tournament", parse(from_os_str))]
+    output: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let opt = Opt::from_args();
+
+    let specs: Vec<PilotSpec> = opt
+        .commands
+        .iter()
+        .map(|c| PilotSpec::Command(c.clone()))
+        .chain(opt.addresses.iter().map(|a| PilotSpec::Address(a.clone())))
+        .collect();
+    if specs.is_empty() {
+        return Err("no pilots given; use --pilot-command or --pilot-address".into());
+    }
+    let names = pilot_names(&specs);
+
+    let runtime = tokio::runtime::Runtime::new()?;
+
+    let mut matches = Vec::new();
+    for (pairing, group) in combinations(specs.len(), opt.pilots_per_match)
+        .into_iter()
+        .enumerate()
+    {
+        for n in 0..opt.matches {
+            let seed = opt.seed + n;
+            let entrants: Vec<(&str, &PilotSpec)> = group
+                .iter()
+                .map(|&i| (names[i].as_str(), &specs[i]))
+                .collect();
+
+            println!(
+                "Match {} of pairing {} (seed {}): {}",
+                n + 1,
+                pairing,
+                seed,
+                entrants
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(" vs ")
+            );
+
+            let (ticks, pilots) = play_match(&entrants, seed, &opt, &runtime)?;
+            matches.push(MatchResult {
+                pairing,
+                seed,
+                ticks,
+                pilots,
+            });
+        }
+    }
+
+    let tournament = Tournament::new(matches);
+    tournament.write_json(BufWriter::new(File::create(
+        opt.output.with_extension("json"),
+    )?))?;
+    tournament.write_csv(BufWriter::new(File::create(
+        opt.output.with_extension("csv"),
+    )?))?;
+
+    println!();
+    println!("pilot  matches  mean score  mean survival time  accuracy");
+    for summary in &tournament.summary {
+        println!(
+            "{}  {}  {:.1}  {:.1}s  {:.0}%",
+            summary.pilot,
+            summary.matches,
+            summary.mean_score,
+            summary.mean_survival_time,
+            summary.accuracy * 100.0
+        );
+    }
+
+    Ok(())
+}
+
+/// Play a single match, returning how many ticks it lasted and each pilot's results.
+fn play_match(
+    entrants: &[(&str, &PilotSpec)],
+    seed: u64,
+    opt: &Opt,
+    runtime: &tokio::runtime::Runtime,
+) -> Result<(u64, Vec<PilotResult>), Box<dyn std::error::Error>> {
+    let game_address = SocketAddr::new([127, 0, 0, 1].into(), free_port()?);
+    let registration_timeout = Duration::from_secs(opt.registration_timeout);
+
+    let mut settings = Settings::load().map_err(|_| "unable to load settings")?;
+    settings.seed = seed;
+    settings.pilot_registration_url = game_address;
+
+    let mut runner = HeadlessRunner::new(settings)?;
+    wait_for_listener(game_address, registration_timeout)?;
+
+    let mut names = HashMap::new();
+    let mut running = Vec::new();
+    for (name, spec) in entrants {
+        let entrant = Entrant::enter(spec, game_address, runtime)?;
+        names.insert(entrant.url.clone(), name.to_string());
+        running.push(entrant);
+    }
+
+    // Pilots register in their own time, so let the game pick up registrations without
+    // moving anything until they're all in.
+    let deadline = Instant::now() + registration_timeout;
+    while runner.simulation().pilot_stats().len() < entrants.len() {
+        if Instant::now() >= deadline {
+            return Err("timed out waiting for pilots to register".into());
+        }
+        runner.simulation_mut().step(Duration::from_secs(0));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // Pilots which disconnect are removed from the game, so keep their last stats. Those which
+    // leave properly have their final stats recorded by the game.
+    let mut stats: HashMap<String, rpc::PilotStats> = HashMap::new();
+    let mut ticks = 0;
+    while ticks < opt.ticks {
+        runner.step();
+        ticks += 1;
+
+        let current = runner.simulation().pilot_stats();
+        let all_out = current.iter().all(|s| s.lives == 0);
+        for s in current {
+            stats.insert(s.pilot.clone(), s);
+        }
+        if all_out || runner.simulation().is_over() {
+            break;
+        }
+    }
+    for s in runner.simulation().departed_pilot_stats() {
+        stats.insert(s.pilot.clone(), s);
+    }
+
+    let results = running
+        .iter()
+        .map(|entrant| {
+            let name = &names[&entrant.url];
+            let stats = stats.get(&entrant.url).cloned().unwrap_or_default();
+            PilotResult::new(name, &stats)
+        })
+        .collect();
+
+    Ok((ticks, results))
+}
+
+/// Names for pilots in the results. Pilots given more than once are numbered so that they can
+/// be told apart.
+fn pilot_names(specs: &[PilotSpec]) -> Vec<String> {
+    let base: Vec<String> = specs
+        .iter()
+        .map(|spec| match spec {
+            PilotSpec::Command(command) => command.clone(),
+            PilotSpec::Address(address) => address.clone(),
+        })
+        .collect();
+
+    base.iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let count = base.iter().filter(|other| *other == name).count();
+            if count > 1 {
+                let n = base[..i].iter().filter(|other| *other == name).count() + 1;
+                format!("{} #{}", name, n)
+            } else {
+                name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Every way of choosing `k` of `n` items, as sorted lists of indices.
+///
+/// If there are fewer than `k` items, they all play together.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 || k >= n {
+        return vec![(0..n).collect()];
+    }
+
+    let mut result = Vec::new();
+    let mut current: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(current.clone());
+
+        // Find the rightmost index that can still be incremented
+        let mut i = k;
+        while i > 0 && current[i - 1] == n - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return result;
+        }
+        current[i - 1] += 1;
+        for j in i..k {
+            current[j] = current[j - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combinations_of_pairs() {
+        assert_eq!(
+            combinations(4, 2),
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+    }
+
+    #[test]
+    fn too_few_pilots_play_together() {
+        assert_eq!(combinations(1, 2), vec![vec![0]]);
+        assert_eq!(combinations(3, 3), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn duplicate_pilots_are_numbered() {
+        let specs = vec![
+            PilotSpec::Command("simple-pilot".to_string()),
+            PilotSpec::Command("driver-pilot".to_string()),
+            PilotSpec::Command("simple-pilot".to_string()),
+        ];
+
+        assert_eq!(
+            pilot_names(&specs),
+            vec!["simple-pilot #1", "driver-pilot", "simple-pilot #2"]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Getting pilots into a match.
+use roid_rage_grpc::roid_rage as rpc;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+/// How to get a pilot into a game.
+#[derive(Clone, Debug)]
+pub enum PilotSpec {
+    /// Start a new pilot process for every match.
+    ///
+    /// The command is run with the address the pilot should listen on and `--server <game
+    /// address>`, which is what pilots built on `pilot_lib::pilot_base::pilot_main` expect.
+    Command(String),
+
+    /// A pilot which is already running at this address. We register it with each game.
+    Address(String),
+}
+
+/// A pilot taking part in a match.
+pub struct Entrant {
+    /// The URL the game knows the pilot by.
+    pub url: String,
+    /// The pilot's process, if we started it. It's killed when the entrant is dropped.
+    child: Option<Child>,
+}
+
+impl Entrant {
+    /// Get the pilot described by `spec` to register with the game at `game_address`.
+    pub fn enter(
+        spec: &PilotSpec,
+        game_address: SocketAddr,
+        runtime: &tokio::runtime::Runtime,
+    ) -> Result<Entrant, Box<dyn std::error::Error>> {
+        match spec {
+            PilotSpec::Command(command) => {
+                let mut words = command.split_whitespace();
+                let program = words.next().ok_or("empty pilot command")?;
+                let pilot_address = SocketAddr::new([127, 0, 0, 1].into(), free_port()?);
+
+                let child = Command::new(program)
+                    .args(words)
+                    .arg(pilot_address.to_string())
+                    .arg("--server")
+                    .arg(game_address.to_string())
+                    .spawn()?;
+
+                Ok(Entrant {
+                    url: format!("http://{}", pilot_address),
+                    child: Some(child),
+                })
+            }
+            PilotSpec::Address(address) => {
+                let url = if address.contains("://") {
+                    address.clone()
+                } else {
+                    format!("http://{}", address)
+                };
+                // The game only accepts registrations while it's running, so this can't
+                // wait for the answer.
+                let registration = url.clone();
+                runtime.spawn(async move {
+                    if let Err(e) = register(game_address, registration.clone()).await {
+                        println!("unable to register {}: {}", registration, e);
+                    }
+                });
+
+                Ok(Entrant { url, child: None })
+            }
+        }
+    }
+}
+
+impl Drop for Entrant {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+async fn register(game_address: SocketAddr, url: String) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = rpc::pilot_registrar_client::PilotRegistrarClient::connect(format!(
+        "http://{}",
+        game_address
+    ))
+    .await?;
+    client
+        .register(rpc::RegistrationRequest {
+            url,
+            protocol_version: rpc::PROTOCOL_VERSION,
+            ..Default::default()
+        })
+        .await?;
+    Ok(())
+}
+
+/// Find a local port that nothing is listening on.
+pub fn free_port() -> std::io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+/// Wait until something accepts connections on `address`.
+pub fn wait_for_listener(address: SocketAddr, timeout: Duration) -> std::io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match TcpStream::connect_timeout(&address, Duration::from_millis(100)) {
+            Ok(_) => return Ok(()),
+            Err(err) if Instant::now() >= deadline => return Err(err),
+            Err(_) => std::thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Tournament results and the files they're written to.
+use roid_rage_grpc::roid_rage as rpc;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// How one pilot did in one match.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PilotResult {
+    pub pilot: String,
+    pub score: u32,
+    pub survival_time: f32,
+    pub accuracy: f32,
+    pub hits: u32,
+    pub shots_fired: u32,
+    pub deaths: u32,
+}
+
+impl PilotResult {
+    pub fn new(pilot: &str, stats: &rpc::PilotStats) -> PilotResult {
+        PilotResult {
+            pilot: pilot.to_string(),
+            score: stats.score,
+            survival_time: stats.survival_time,
+            accuracy: stats.accuracy,
+            hits: stats.hits,
+            shots_fired: stats.shots_fired,
+            deaths: stats.deaths,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MatchResult {
+    /// Which group of pilots played the match.
+    pub pairing: usize,
+    pub seed: u64,
+    pub ticks: u64,
+    pub pilots: Vec<PilotResult>,
+}
+
+/// A pilot's results over the whole tournament.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub pilot: String,
+    pub matches: u32,
+    pub total_score: u32,
+    pub mean_score: f32,
+    pub mean_survival_time: f32,
+    /// Hits over shots fired across all matches.
+    pub accuracy: f32,
+}
+
+#[derive(Serialize)]
+pub struct Tournament {
+    pub summary: Vec<Summary>,
+    pub matches: Vec<MatchResult>,
+}
+
+impl Tournament {
+    pub fn new(matches: Vec<MatchResult>) -> Tournament {
+        Tournament {
+            summary: summarize(&matches),
+            matches,
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::from)
+    }
+
+    /// Write one row per pilot per match.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "pairing,seed,pilot,score,survival_time,accuracy,hits,shots_fired,deaths"
+        )?;
+        for m in &self.matches {
+            for p in &m.pilots {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{},{},{}",
+                    m.pairing,
+                    m.seed,
+                    csv_field(&p.pilot),
+                    p.score,
+                    p.survival_time,
+                    p.accuracy,
+                    p.hits,
+                    p.shots_fired,
+                    p.deaths
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Total up each pilot's results, best mean score first.
+pub fn summarize(matches: &[MatchResult]) -> Vec<Summary> {
+    let mut pilots: Vec<String> = Vec::new();
+    for result in matches.iter().flat_map(|m| &m.pilots) {
+        if !pilots.contains(&result.pilot) {
+            pilots.push(result.pilot.clone());
+        }
+    }
+
+    let mut summary: Vec<Summary> = pilots
+        .into_iter()
+        .map(|pilot| {
+            let results: Vec<&PilotResult> = matches
+                .iter()
+                .flat_map(|m| &m.pilots)
+                .filter(|r| r.pilot == pilot)
+                .collect();
+            let count = results.len() as u32;
+            let total_score = results.iter().map(|r| r.score).sum();
+            let survival_time: f32 = results.iter().map(|r| r.survival_time).sum();
+            let hits: u32 = results.iter().map(|r| r.hits).sum();
+            let shots: u32 = results.iter().map(|r| r.shots_fired).sum();
+
+            Summary {
+                pilot,
+                matches: count,
+                total_score,
+                mean_score: total_score as f32 / count as f32,
+                mean_survival_time: survival_time / count as f32,
+                accuracy: if shots == 0 {
+                    0.0
+                } else {
+                    hits as f32 / shots as f32
+                },
+            }
+        })
+        .collect();
+
+    summary.sort_by(|a, b| b.mean_score.partial_cmp(&a.mean_score).unwrap());
+    summary
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(pilot: &str, score: u32, hits: u32, shots_fired: u32) -> PilotResult {
+        PilotResult {
+            pilot: pilot.to_string(),
+            score,
+            survival_time: 10.0,
+            accuracy: 0.0,
+            hits,
+            shots_fired,
+            deaths: 1,
+        }
+    }
+
+    fn matches() -> Vec<MatchResult> {
+        vec![
+            MatchResult {
+                pairing: 0,
+                seed: 1,
+                ticks: 100,
+                pilots: vec![result("a", 100, 1, 4), result("b", 300, 3, 4)],
+            },
+            MatchResult {
+                pairing: 0,
+                seed: 2,
+                ticks: 100,
+                pilots: vec![result("a", 200, 1, 4), result("b", 100, 1, 4)],
+            },
+        ]
+    }
+
+    #[test]
+    fn summary_is_sorted_by_mean_score() {
+        let summary = summarize(&matches());
+
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].pilot, "b");
+        assert_eq!(summary[0].matches, 2);
+        assert_eq!(summary[0].total_score, 400);
+        assert_eq!(summary[0].mean_score, 200.0);
+        assert_eq!(summary[0].accuracy, 0.5);
+        assert_eq!(summary[1].pilot, "a");
+        assert_eq!(summary[1].mean_score, 150.0);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_pilot_per_match() {
+        let mut csv = Vec::new();
+        Tournament::new(matches()).write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        assert_eq!(csv.lines().count(), 5);
+        assert_eq!(csv.lines().nth(1).unwrap(), "0,1,a,100,10,0,1,4,1");
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("simple-pilot"), "simple-pilot");
+        assert_eq!(csv_field("pilot --x 1,2"), "\"pilot --x 1,2\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn json_includes_summary_and_matches() {
+        let mut json = Vec::new();
+        Tournament::new(matches()).write_json(&mut json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(value["summary"][0]["pilot"], "b");
+        assert_eq!(value["matches"][1]["seed"], 2);
+    }
+}
@@ -1,17 +1,22 @@
-use super::collision_groups::{ROID_GROUP, WEAPON_GROUP};
-use crate::components::{CollisionHandle, LinearVelocity, Position};
+use super::collision_groups::{ROID_GROUP, SHIP_GROUP, WEAPON_GROUP};
+use crate::components::{CollisionHandle, LinearVelocity, Position, Weapon};
 use glam::Vec2;
 use ncollide2d::na::{Isometry2, Vector2};
 use ncollide2d::pipeline::{CollisionGroups, GeometricQueryType};
 use ncollide2d::shape::{Ball, ShapeHandle};
 use ncollide2d::world::CollisionWorld;
-use specs::{Component, HashMapStorage};
+use specs::{Component, Entity, HashMapStorage};
 
-pub struct Bullet {}
+pub struct Bullet {
+    /// The pilot who fired the bullet.
+    pub owner: Entity,
+    /// The weapon that fired it.
+    pub weapon: Weapon,
+}
 
 impl Bullet {
-    pub fn new() -> Self {
-        Bullet {}
+    pub fn new(owner: Entity, weapon: Weapon) -> Self {
+        Bullet { owner, weapon }
     }
 
     pub fn radius() -> f32 {
@@ -23,19 +28,33 @@ impl Component for Bullet {
     type Storage = HashMapStorage<Self>;
 }
 
+/// Collision groups for anything fired by a ship. In PvP games these can hit ships as well as
+/// roids.
+pub fn weapon_collision_groups(pvp: bool) -> CollisionGroups {
+    let mut collision_groups = CollisionGroups::new();
+    collision_groups.set_membership(&[WEAPON_GROUP]);
+    if pvp {
+        collision_groups.set_whitelist(&[ROID_GROUP, SHIP_GROUP]);
+    } else {
+        collision_groups.set_whitelist(&[ROID_GROUP]);
+    }
+    collision_groups
+}
+
 pub fn make_bullet<B>(
     builder: B,
+    owner: Entity,
+    weapon: Weapon,
     pos: Vec2,
     velocity: Vec2,
+    pvp: bool,
     collision_world: &mut CollisionWorld<f32, specs::world::Index>,
 ) where
     B: specs::world::Builder,
 {
     let position = Position(pos);
 
-    let mut collision_groups = CollisionGroups::new();
-    collision_groups.set_membership(&[WEAPON_GROUP]);
-    collision_groups.set_whitelist(&[ROID_GROUP]);
+    let collision_groups = weapon_collision_groups(pvp);
 
     let collision_shape = ShapeHandle::new(Ball::new(Bullet::radius()));
 
@@ -50,7 +69,7 @@ pub fn make_bullet<B>(
 
     // Create the entity
     let entity = builder
-        .with(Bullet::new())
+        .with(Bullet::new(owner, weapon))
         .with(LinearVelocity(velocity))
         .with(position)
         .with(CollisionHandle(collision_handle))
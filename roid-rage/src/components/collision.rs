@@ -1,10 +1,14 @@
 use specs::Component;
-use specs::NullStorage;
+use specs::DenseVecStorage;
+use specs::Entity;
 
 /// Created for entities that have collided with something
 #[derive(Default)]
-pub struct Collision;
+pub struct Collision {
+    /// The entities this one collided with.
+    pub with: Vec<Entity>,
+}
 
 impl Component for Collision {
-    type Storage = NullStorage<Self>;
+    type Storage = DenseVecStorage<Self>;
 }
@@ -2,3 +2,4 @@ pub const ROID_GROUP: usize = 1;
 pub const SHIP_GROUP: usize = 2;
 pub const WEAPON_GROUP: usize = 3;
 // pub const DEBRIS_GROUP: usize = 4;
+pub const POWERUP_GROUP: usize = 5;
@@ -0,0 +1,5 @@
+use roid_rage_grpc::roid_rage as rpc;
+
+/// Final statistics of the pilots which have left the game, in the order they left.
+#[derive(Default)]
+pub struct DepartedPilots(pub Vec<rpc::PilotStats>);
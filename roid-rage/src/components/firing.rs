@@ -0,0 +1,10 @@
+use specs::Component;
+use specs::NullStorage;
+
+/// Marks a ship whose pilot fired its cannon this tick.
+#[derive(Default)]
+pub struct Firing;
+
+impl Component for Firing {
+    type Storage = NullStorage<Self>;
+}
@@ -18,6 +18,7 @@ impl Fragment {
         }
     }
 
+    #[cfg(feature = "render")]
     pub fn radius() -> f32 {
         1.0
     }
@@ -27,11 +28,12 @@ impl Component for Fragment {
     type Storage = HashMapStorage<Self>;
 }
 
-pub fn make_fragment<B>(builder: B, x: f32, y: f32, course: f32)
+pub fn make_fragment<B, R>(builder: B, x: f32, y: f32, course: f32, rng: &mut R)
 where
     B: specs::world::Builder,
+    R: Rng + ?Sized,
 {
-    let speed = rand::rng().random::<f32>() * 250.0 + 250.0;
+    let speed = rng.random::<f32>() * 250.0 + 250.0;
 
     builder
         .with(LinearVelocity(from_quantity_and_bearing(speed, course)))
@@ -0,0 +1,12 @@
+use crate::game_mode::Outcome;
+
+/// How far through the game we are.
+#[derive(Default)]
+pub struct GameProgress {
+    /// The current wave of roids, counting from 1. This is 0 until the first wave arrives.
+    pub wave: u32,
+    /// Seconds of game time since the first pilot joined.
+    pub elapsed: f32,
+    /// Why the game ended, once it has.
+    pub outcome: Option<Outcome>,
+}
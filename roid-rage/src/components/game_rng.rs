@@ -0,0 +1,14 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The source of all randomness in the simulation.
+///
+/// Every random choice the game makes is drawn from this resource, so two games started from
+/// the same seed and fed the same pilot commands play out identically.
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        GameRng(StdRng::seed_from_u64(seed))
+    }
+}
@@ -0,0 +1,101 @@
+use crate::settings::Settings;
+use roid_rage_grpc::roid_rage as rpc;
+use specs::{Component, HashMapStorage};
+
+/// How much punishment a ship can take.
+///
+/// Damage is soaked up by the shield first, which recharges over time, and then by the hull's hit
+/// points, which don't. A ship with no hit points left is destroyed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Health {
+    pub hit_points: f32,
+    pub max_hit_points: f32,
+    pub shield: f32,
+    pub max_shield: f32,
+}
+
+impl Health {
+    /// A freshly launched ship, undamaged and fully shielded.
+    pub fn from_settings(settings: &Settings) -> Health {
+        Health {
+            hit_points: settings.ship_hit_points,
+            max_hit_points: settings.ship_hit_points,
+            shield: settings.ship_shield,
+            max_shield: settings.ship_shield,
+        }
+    }
+
+    /// Take `amount` damage, returning whether the ship is destroyed.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        let absorbed = amount.min(self.shield);
+        self.shield -= absorbed;
+        self.hit_points -= amount - absorbed;
+        self.hit_points <= 0.0
+    }
+
+    /// Recharge the shield over `dt` seconds.
+    pub fn recharge(&mut self, dt: f32, settings: &Settings) {
+        self.shield = (self.shield + settings.ship_shield_recharge_rate * dt).min(self.max_shield);
+    }
+
+    pub fn to_rpc(&self) -> rpc::Health {
+        rpc::Health {
+            hit_points: self.hit_points,
+            max_hit_points: self.max_hit_points,
+            shield: self.shield,
+            max_shield: self.max_shield,
+        }
+    }
+}
+
+impl Component for Health {
+    type Storage = HashMapStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health() -> Health {
+        Health {
+            hit_points: 10.0,
+            max_hit_points: 10.0,
+            shield: 5.0,
+            max_shield: 5.0,
+        }
+    }
+
+    #[test]
+    fn shield_absorbs_damage_first() {
+        let mut health = health();
+
+        assert!(!health.damage(8.0));
+        assert_eq!(health.shield, 0.0);
+        assert_eq!(health.hit_points, 7.0);
+
+        assert!(health.damage(7.0));
+    }
+
+    #[test]
+    fn shield_recharges_up_to_its_maximum() {
+        let mut settings = Settings::load().expect("settings");
+        settings.ship_shield_recharge_rate = 2.0;
+        let mut health = health();
+        health.shield = 1.0;
+
+        health.recharge(1.0, &settings);
+        assert_eq!(health.shield, 3.0);
+
+        health.recharge(10.0, &settings);
+        assert_eq!(health.shield, 5.0);
+    }
+
+    #[test]
+    fn no_hit_points_means_any_collision_destroys() {
+        let mut settings = Settings::load().expect("settings");
+        settings.ship_hit_points = 0.0;
+        settings.ship_shield = 0.0;
+
+        assert!(Health::from_settings(&settings).damage(0.0));
+    }
+}
@@ -0,0 +1,7 @@
+/// Seconds remaining during which a ship can't be damaged.
+#[derive(Clone, Copy, Debug)]
+pub struct Invulnerable(pub f32);
+
+impl specs::Component for Invulnerable {
+    type Storage = specs::HashMapStorage<Self>;
+}
@@ -0,0 +1,22 @@
+use glam::Vec2;
+use specs::{Component, HashMapStorage};
+
+/// The path of a laser shot. Lasers hit instantly, so this is only kept around long enough to
+/// be seen.
+pub struct LaserBeam {
+    pub start: Vec2,
+    pub end: Vec2,
+    /// Seconds until it fades.
+    pub time_left: f32,
+}
+
+impl LaserBeam {
+    /// Seconds a beam stays visible.
+    pub fn duration() -> f32 {
+        0.1
+    }
+}
+
+impl Component for LaserBeam {
+    type Storage = HashMapStorage<Self>;
+}
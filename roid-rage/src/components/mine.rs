@@ -0,0 +1,73 @@
+use super::collision_groups::WEAPON_GROUP;
+use crate::components::{Bullet, CollisionHandle, LinearVelocity, Position, Weapon};
+use glam::Vec2;
+use ncollide2d::na::{Isometry2, Vector2};
+use ncollide2d::pipeline::{CollisionGroups, GeometricQueryType};
+use ncollide2d::shape::{Ball, ShapeHandle};
+use ncollide2d::world::CollisionWorld;
+use specs::{Component, Entity, HashMapStorage};
+
+/// A stationary weapon. It can't hit anything until it has armed itself.
+pub struct Mine {
+    /// Seconds until it's armed.
+    pub time_to_arm: f32,
+    /// Seconds until it disappears.
+    pub time_left: f32,
+}
+
+impl Mine {
+    pub fn radius() -> f32 {
+        4.0
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.time_to_arm <= 0.0
+    }
+}
+
+impl Component for Mine {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Lay a mine. It's a bullet which doesn't move, so it explodes and credits its owner like one.
+pub fn make_mine<B>(
+    builder: B,
+    owner: Entity,
+    pos: Vec2,
+    arm_delay: f32,
+    lifetime: f32,
+    collision_world: &mut CollisionWorld<f32, specs::world::Index>,
+) where
+    B: specs::world::Builder,
+{
+    // Nothing can touch it until it's armed
+    let mut collision_groups = CollisionGroups::new();
+    collision_groups.set_membership(&[WEAPON_GROUP]);
+    collision_groups.set_whitelist(&[]);
+
+    let collision_shape = ShapeHandle::new(Ball::new(Mine::radius()));
+
+    // Put entry in collision world
+    let (collision_handle, obj) = collision_world.add(
+        Isometry2::new(Vector2::<f32>::new(pos.x, pos.y), 0.0f32),
+        collision_shape,
+        collision_groups,
+        GeometricQueryType::Contacts(0.0, 0.0),
+        0,
+    );
+
+    // Create the entity
+    let entity = builder
+        .with(Mine {
+            time_to_arm: arm_delay,
+            time_left: lifetime,
+        })
+        .with(Bullet::new(owner, Weapon::Mine))
+        .with(LinearVelocity(Vec2::ZERO))
+        .with(Position(pos))
+        .with(CollisionHandle(collision_handle))
+        .build();
+
+    // Annotate the collision object with the entity's ID
+    *obj.data_mut() = entity.id();
+}
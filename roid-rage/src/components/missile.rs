@@ -0,0 +1,11 @@
+use specs::{Component, HashMapStorage};
+
+/// A bullet which steers itself towards the nearest target.
+pub struct Missile {
+    /// Seconds until it runs out of fuel and disappears.
+    pub time_left: f32,
+}
+
+impl Component for Missile {
+    type Storage = HashMapStorage<Self>;
+}
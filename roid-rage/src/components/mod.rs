@@ -1,16 +1,31 @@
 pub use self::angular_velocity::AngularVelocity;
-pub use self::bullet::{make_bullet, Bullet};
+pub use self::bullet::{make_bullet, weapon_collision_groups, Bullet};
 pub use self::collision::Collision;
 pub use self::collision_handle::CollisionHandle;
+pub use self::departed_pilots::DepartedPilots;
 pub use self::fire_timer::FireTimer;
+pub use self::firing::Firing;
 pub use self::fragment::{make_fragment, Fragment};
+pub use self::game_progress::GameProgress;
+pub use self::game_rng::GameRng;
+pub use self::health::Health;
+pub use self::invulnerable::Invulnerable;
+pub use self::laser_beam::LaserBeam;
 pub use self::linear_velocity::LinearVelocity;
+pub use self::mine::{make_mine, Mine};
+pub use self::missile::Missile;
 pub use self::pilot::Pilot;
 pub use self::position::Position;
+pub use self::power_up::{make_power_up, PowerUp, PowerUpKind};
+pub use self::power_up_effects::PowerUpEffects;
+pub use self::respawn_timer::RespawnTimer;
 pub use self::roid::{make_roid, Roid};
 pub use self::rotation::Rotation;
-pub use self::ship::{make_ship, Ship, Cannon};
+pub use self::ship::{make_ship, Cannon, Ship};
+pub use self::ship_resources::ShipResources;
+pub use self::stats::Stats;
 pub use self::time_delta::TimeDelta;
+pub use self::weapon::Weapon;
 pub use self::wrapping::Wrapping;
 
 mod angular_velocity;
@@ -18,13 +33,28 @@ mod bullet;
 mod collision;
 mod collision_groups;
 mod collision_handle;
+mod departed_pilots;
 mod fire_timer;
+mod firing;
 mod fragment;
+mod game_progress;
+mod game_rng;
+mod health;
+mod invulnerable;
+mod laser_beam;
 mod linear_velocity;
+mod mine;
+mod missile;
 mod pilot;
 mod position;
+mod power_up;
+mod power_up_effects;
+mod respawn_timer;
 mod roid;
 mod rotation;
 mod ship;
+mod ship_resources;
+mod stats;
 mod time_delta;
+mod weapon;
 mod wrapping;
@@ -1,18 +1,69 @@
+use crate::pilot_connection::PilotConnection;
+use roid_rage_grpc::roid_rage as rpc;
 use specs::{Component, VecStorage};
 
 pub struct Pilot {
     /// Address for communicating with pilot process with grpc.
     pub url: String,
+
+    /// Name to show for the pilot. This is the url unless the pilot gave one when registering.
+    pub name: String,
+
+    /// Team the pilot says it's on, if any.
+    pub team: String,
+
+    /// Colour to draw the pilot's ship in, if it asked for one.
+    pub color: Option<rpc::Color>,
+
+    /// Number of ships the pilot has left, including the current one.
+    pub lives: u32,
+
+    /// Streaming connection to the pilot process, once one has been opened.
+    pub connection: Option<PilotConnection>,
+
+    /// Whether the game can open a new connection if this one drops. It can't for pilots which
+    /// connected to the game themselves.
+    pub can_reconnect: bool,
+
+    /// The last command received from the pilot in time.
+    pub last_command: Option<rpc::Command>,
+
+    /// Consecutive ticks on which the pilot missed its deadline or its connection failed.
+    pub strikes: u32,
 }
 
 impl Pilot {
-    pub fn new(url: &str) -> Pilot {
+    pub fn new(url: &str, lives: u32) -> Pilot {
         Pilot {
             url: url.to_string(),
+            name: url.to_string(),
+            team: String::new(),
+            color: None,
+            lives,
+            connection: None,
+            can_reconnect: true,
+            last_command: None,
+            strikes: 0,
         }
     }
 }
 
+impl Pilot {
+    /// The pilot's name, along with its team if it has one.
+    pub fn display_name(&self) -> String {
+        if self.team.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} [{}]", self.name, self.team)
+        }
+    }
+
+    /// Whether the two pilots are on the same team. Pilots without a team have no team-mates.
+    pub fn is_teammate(&self, other: &Pilot) -> bool {
+        !self.team.is_empty() && self.team == other.team
+    }
+}
+
 impl Component for Pilot {
     type Storage = VecStorage<Self>;
 }
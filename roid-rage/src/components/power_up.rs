@@ -0,0 +1,166 @@
+use super::collision_groups::{POWERUP_GROUP, SHIP_GROUP};
+use crate::components::{CollisionHandle, Position};
+use crate::settings::Settings;
+use glam::Vec2;
+use ncollide2d::na::{Isometry2, Vector2};
+use ncollide2d::pipeline::{CollisionGroups, GeometricQueryType};
+use ncollide2d::shape::{Ball, ShapeHandle};
+use ncollide2d::world::CollisionWorld;
+use rand::Rng;
+use roid_rage_grpc::roid_rage as rpc;
+use specs::{Component, HashMapStorage};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerUpKind {
+    /// The cannon reloads twice as fast for a while.
+    RapidFire,
+    /// The cannon fires three bullets at once for a while.
+    SpreadShot,
+    /// Nothing can destroy the ship for a while.
+    Shield,
+    /// The pilot gets another ship.
+    ExtraLife,
+    /// The ship's fuel tank is filled up.
+    FuelRefill,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::RapidFire,
+        PowerUpKind::SpreadShot,
+        PowerUpKind::Shield,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::FuelRefill,
+    ];
+
+    /// How likely this kind is to be dropped, relative to the others.
+    fn drop_weight(&self, settings: &Settings) -> f32 {
+        match self {
+            PowerUpKind::RapidFire => settings.rapid_fire_drop_weight,
+            PowerUpKind::SpreadShot => settings.spread_shot_drop_weight,
+            PowerUpKind::Shield => settings.shield_drop_weight,
+            PowerUpKind::ExtraLife => settings.extra_life_drop_weight,
+            PowerUpKind::FuelRefill => settings.fuel_refill_drop_weight,
+        }
+        .max(0.0)
+    }
+
+    /// The power-up, if any, dropped by a destroyed roid.
+    pub fn random_drop<R: Rng + ?Sized>(settings: &Settings, rng: &mut R) -> Option<PowerUpKind> {
+        if rng.random::<f32>() >= settings.power_up_drop_chance {
+            return None;
+        }
+
+        let total: f32 = PowerUpKind::ALL
+            .iter()
+            .map(|kind| kind.drop_weight(settings))
+            .sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut choice = rng.random::<f32>() * total;
+        for kind in PowerUpKind::ALL.iter() {
+            choice -= kind.drop_weight(settings);
+            if choice < 0.0 {
+                return Some(*kind);
+            }
+        }
+        PowerUpKind::ALL.last().copied()
+    }
+
+    pub fn to_rpc(self) -> rpc::PowerUpKind {
+        match self {
+            PowerUpKind::RapidFire => rpc::PowerUpKind::RapidFire,
+            PowerUpKind::SpreadShot => rpc::PowerUpKind::SpreadShot,
+            PowerUpKind::Shield => rpc::PowerUpKind::Shield,
+            PowerUpKind::ExtraLife => rpc::PowerUpKind::ExtraLife,
+            PowerUpKind::FuelRefill => rpc::PowerUpKind::FuelRefill,
+        }
+    }
+}
+
+/// Something a ship can fly into to collect.
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    /// Seconds until it disappears.
+    pub time_left: f32,
+}
+
+impl PowerUp {
+    pub fn radius() -> f32 {
+        6.0
+    }
+}
+
+impl Component for PowerUp {
+    type Storage = HashMapStorage<Self>;
+}
+
+pub fn make_power_up<B>(
+    builder: B,
+    kind: PowerUpKind,
+    pos: Vec2,
+    lifetime: f32,
+    collision_world: &mut CollisionWorld<f32, specs::world::Index>,
+) where
+    B: specs::world::Builder,
+{
+    let mut collision_groups = CollisionGroups::new();
+    collision_groups.set_membership(&[POWERUP_GROUP]);
+    collision_groups.set_whitelist(&[SHIP_GROUP]);
+
+    let collision_shape = ShapeHandle::new(Ball::new(PowerUp::radius()));
+
+    // Put entry in collision world
+    let (collision_handle, obj) = collision_world.add(
+        Isometry2::new(Vector2::<f32>::new(pos.x, pos.y), 0.0f32),
+        collision_shape,
+        collision_groups,
+        GeometricQueryType::Contacts(0.0, 0.0),
+        0,
+    );
+
+    // Create the entity
+    let entity = builder
+        .with(PowerUp {
+            kind,
+            time_left: lifetime,
+        })
+        .with(Position(pos))
+        .with(CollisionHandle(collision_handle))
+        .build();
+
+    // Annotate the collision object with the entity's ID
+    *obj.data_mut() = entity.id();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::GameRng;
+
+    #[test]
+    fn nothing_drops_without_a_chance() {
+        let mut settings = Settings::load().expect("settings");
+        settings.power_up_drop_chance = 0.0;
+        let mut rng = GameRng::from_seed(1);
+
+        assert!((0..100).all(|_| PowerUpKind::random_drop(&settings, &mut rng.0).is_none()));
+    }
+
+    #[test]
+    fn drops_follow_weights() {
+        let mut settings = Settings::load().expect("settings");
+        settings.power_up_drop_chance = 1.0;
+        settings.rapid_fire_drop_weight = 0.0;
+        settings.spread_shot_drop_weight = 0.0;
+        settings.shield_drop_weight = 1.0;
+        settings.extra_life_drop_weight = 0.0;
+        settings.fuel_refill_drop_weight = 0.0;
+        let mut rng = GameRng::from_seed(1);
+
+        assert!((0..100)
+            .all(|_| PowerUpKind::random_drop(&settings, &mut rng.0) == Some(PowerUpKind::Shield)));
+    }
+}
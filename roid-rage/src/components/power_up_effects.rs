@@ -0,0 +1,10 @@
+/// Seconds left on the timed power-ups a ship has collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PowerUpEffects {
+    pub rapid_fire: f32,
+    pub spread_shot: f32,
+}
+
+impl specs::Component for PowerUpEffects {
+    type Storage = specs::HashMapStorage<Self>;
+}
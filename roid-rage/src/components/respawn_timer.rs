@@ -0,0 +1,7 @@
+/// Seconds remaining before a pilot's destroyed ship is recreated.
+#[derive(Clone, Copy, Debug)]
+pub struct RespawnTimer(pub f32);
+
+impl specs::Component for RespawnTimer {
+    type Storage = specs::HashMapStorage<Self>;
+}
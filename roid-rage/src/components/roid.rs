@@ -3,22 +3,24 @@ use crate::components::{
     AngularVelocity, CollisionHandle, LinearVelocity, Position, Rotation, Wrapping,
 };
 use crate::core::util::from_quantity_and_bearing;
+use crate::settings::Settings;
 use glam::Vec2;
-use ncollide2d::na::{zero, Isometry2, Vector2};
+use ncollide2d::na::{zero, Isometry2, Point2, Vector2};
 use ncollide2d::pipeline::{CollisionGroups, GeometricQueryType};
-use ncollide2d::shape::{Ball, ShapeHandle};
+use ncollide2d::shape::{Ball, ConvexPolygon, ShapeHandle};
 use ncollide2d::world::CollisionWorld;
 use rand::Rng;
 use specs::{Component, VecStorage};
+use std::f32::consts::PI;
 
 pub struct Roid {
     pub radius: f32,
+    /// Distance from the center of each point on the outline, evenly spaced by angle.
     pub points: Vec<f32>,
 }
 
 impl Roid {
-    pub fn new(radius: f32, bumpiness: f32) -> Self {
-        let mut rng = rand::rng();
+    pub fn new<R: Rng + ?Sized>(radius: f32, bumpiness: f32, rng: &mut R) -> Self {
         let num_points = rng.random::<u32>() % 5 + 5;
         let point_variance = radius * bumpiness;
 
@@ -37,13 +39,51 @@ impl Roid {
             points: points,
         }
     }
+
+    /// The points of the roid's outline, relative to its center and before rotation.
+    pub fn outline(&self) -> Vec<Vec2> {
+        let angle_step = (PI * 2.0) / self.points.len() as f32;
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(i, distance)| from_quantity_and_bearing(*distance, angle_step * i as f32))
+            .collect()
+    }
+
+    /// The collision shape for the roid: the convex hull of its outline.
+    pub fn collision_shape(&self) -> ShapeHandle<f32> {
+        let outline: Vec<Point2<f32>> = self
+            .outline()
+            .iter()
+            .map(|p| Point2::new(p.x, p.y))
+            .collect();
+
+        match ConvexPolygon::try_from_points(&outline) {
+            Some(polygon) => ShapeHandle::new(polygon),
+            None => ShapeHandle::new(Ball::new(self.radius)),
+        }
+    }
+
+    /// The points awarded for destroying this roid.
+    ///
+    /// Roids come in three sizes: large ones split into medium ones, which split into small
+    /// ones, which are destroyed outright. Smaller roids are harder to hit so they're worth more.
+    pub fn points(&self, settings: &Settings) -> u32 {
+        if self.radius < settings.minimum_roid_radius {
+            settings.small_roid_points
+        } else if self.radius <= settings.maximum_roid_radius / 2.0 {
+            settings.medium_roid_points
+        } else {
+            settings.large_roid_points
+        }
+    }
 }
 
 impl Component for Roid {
     type Storage = VecStorage<Self>;
 }
 
-pub fn make_roid<B>(
+pub fn make_roid<B, R>(
     builder: B,
     x: f32,
     y: f32,
@@ -52,9 +92,11 @@ pub fn make_roid<B>(
     angular_velocity: f32,
     radius: f32,
     bumpiness: f32,
+    rng: &mut R,
     collision_world: &mut CollisionWorld<f32, specs::world::Index>,
 ) where
     B: specs::world::Builder,
+    R: Rng + ?Sized,
 {
     let position = Position(Vec2::new(x, y));
     let rotation = Rotation(0.0);
@@ -66,7 +108,8 @@ pub fn make_roid<B>(
     let collision_isometry =
         Isometry2::new(Vector2::<f32>::new(position.0.x, position.0.y), zero());
 
-    let collision_shape = ShapeHandle::new(Ball::new(radius));
+    let roid = Roid::new(radius, bumpiness, rng);
+    let collision_shape = roid.collision_shape();
 
     // Put entry in collision world
     let (collision_handle, obj) = collision_world.add(
@@ -85,9 +128,55 @@ pub fn make_roid<B>(
         .with(rotation)
         .with(Wrapping)
         .with(CollisionHandle(collision_handle))
-        .with(Roid::new(radius, bumpiness))
+        .with(roid)
         .build();
 
     // Annotate the collision object with the entity's ID
     *obj.data_mut() = entity.id();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Roid;
+    use crate::settings::Settings;
+    use ncollide2d::shape::ConvexPolygon;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn smaller_roids_are_worth_more() {
+        let settings = Settings::load().expect("settings");
+        let mut rng = StdRng::seed_from_u64(1);
+        let large = Roid::new(settings.maximum_roid_radius, 0.0, &mut rng);
+        let medium = Roid::new(settings.maximum_roid_radius / 2.0, 0.0, &mut rng);
+        let small = Roid::new(settings.maximum_roid_radius / 4.0, 0.0, &mut rng);
+
+        assert_eq!(large.points(&settings), settings.large_roid_points);
+        assert_eq!(medium.points(&settings), settings.medium_roid_points);
+        assert_eq!(small.points(&settings), settings.small_roid_points);
+    }
+
+    #[test]
+    fn outline_follows_points() {
+        let roid = Roid::new(10.0, 0.2, &mut StdRng::seed_from_u64(1));
+        let outline = roid.outline();
+
+        assert_eq!(outline.len(), roid.points.len());
+        for (point, distance) in outline.iter().zip(&roid.points) {
+            assert!((point.length() - distance).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn collision_shape_is_polygon() {
+        let roid = Roid::new(10.0, 0.2, &mut StdRng::seed_from_u64(1));
+        let shape = roid.collision_shape();
+        let polygon = shape
+            .as_shape::<ConvexPolygon<f32>>()
+            .expect("roid collision shape should be a polygon");
+
+        for point in polygon.points() {
+            assert!(point.coords.norm() <= 10.0 * 1.2 + 0.0001);
+        }
+    }
+}
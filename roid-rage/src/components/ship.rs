@@ -1,10 +1,31 @@
-use crate::components::{AngularVelocity, LinearVelocity, Position, Rotation, Wrapping};
+use super::collision_groups::{POWERUP_GROUP, ROID_GROUP, SHIP_GROUP, WEAPON_GROUP};
+use crate::components::{
+    AngularVelocity, CollisionHandle, LinearVelocity, Position, Rotation, Weapon, Wrapping,
+};
+use crate::settings::Settings;
 use glam::Vec2;
+use ncollide2d::na::{Isometry2, Point2, Vector2};
+use ncollide2d::pipeline::{CollisionGroups, GeometricQueryType};
+use ncollide2d::shape::{Ball, ConvexPolygon, ShapeHandle};
+use ncollide2d::world::CollisionWorld;
 use specs::{Component, HashMapStorage};
 
+/// The ship's cannon, with the parameters of the weapon currently selected.
 pub struct Cannon {
     pub bullet_speed: f32,
     pub rate_of_fire: f32,
+    pub weapon: Weapon,
+}
+
+impl Cannon {
+    /// A cannon firing `weapon`, as described by the settings.
+    pub fn with_weapon(weapon: Weapon, settings: &Settings) -> Cannon {
+        Cannon {
+            bullet_speed: weapon.projectile_speed(settings),
+            rate_of_fire: weapon.rate_of_fire(settings),
+            weapon,
+        }
+    }
 }
 
 pub struct Ship {
@@ -13,6 +34,10 @@ pub struct Ship {
     pub mass: f32,
     pub thrust: f32,
     pub rotational_speed: f32,
+    /// Thrust of the retro thrusters, which push the ship backwards.
+    pub reverse_thrust: f32,
+    /// Thrust of the side thrusters.
+    pub strafe_thrust: f32,
     pub cannon: Cannon,
 }
 
@@ -31,9 +56,55 @@ impl Ship {
             mass: mass,
             thrust: thrust,
             rotational_speed: rotational_speed,
+            reverse_thrust: 0.0,
+            strafe_thrust: 0.0,
             cannon: cannon,
         }
     }
+
+    /// A ship as described by the settings.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Ship {
+            reverse_thrust: settings.ship_reverse_thrust,
+            strafe_thrust: settings.ship_strafe_thrust,
+            ..Ship::new(
+                settings.ship_length,
+                settings.ship_width,
+                settings.ship_mass,
+                settings.ship_thrust,
+                settings.ship_rotational_speed,
+                Cannon::with_weapon(Weapon::Gun, settings),
+            )
+        }
+    }
+
+    /// The corners of the ship's hull, relative to its center and pointing along the x-axis.
+    pub fn hull(&self) -> [Vec2; 3] {
+        [
+            Vec2::new(self.length / 2.0, 0.0),
+            Vec2::new(-self.length / 2.0, -self.width / 2.0),
+            Vec2::new(-self.length / 2.0, self.width / 2.0),
+        ]
+    }
+
+    /// The collision shape for the ship: its hull, or a ball around it if the ship's dimensions
+    /// don't make a proper triangle.
+    pub fn collision_shape(&self) -> ShapeHandle<f32> {
+        // ncollide makes a flat polygon from a flat hull, or panics if the hull is a single point,
+        // so we check for those ourselves.
+        let polygon = if self.length > 0.0 && self.width > 0.0 {
+            let hull: Vec<Point2<f32>> =
+                self.hull().iter().map(|p| Point2::new(p.x, p.y)).collect();
+            ConvexPolygon::try_from_points(&hull)
+        } else {
+            None
+        };
+
+        match polygon {
+            Some(polygon) => ShapeHandle::new(polygon),
+            None => ShapeHandle::new(Ball::new(self.length.max(self.width) / 2.0)),
+        }
+    }
 }
 
 impl Component for Ship {
@@ -42,36 +113,83 @@ impl Component for Ship {
 
 pub fn make_ship<B>(
     builder: B,
+    ship: Ship,
     heading: f32,
-    length: f32,
-    width: f32,
-    mass: f32,
-    thrust: f32,
-    rotational_speed: f32,
-    cannon: Cannon,
     x: f32,
     y: f32,
     velocity: Vec2,
-) -> B
-where
+    pvp: bool,
+    collision_world: &mut CollisionWorld<f32, specs::world::Index>,
+) where
     B: specs::world::Builder,
 {
     let position = Position(Vec2::new(x, y));
     let rotation = Rotation(heading);
 
+    let mut collision_groups = CollisionGroups::new();
+    collision_groups.set_membership(&[SHIP_GROUP]);
+    if pvp {
+        // Ships can ram each other and be shot.
+        collision_groups.set_whitelist(&[ROID_GROUP, SHIP_GROUP, WEAPON_GROUP, POWERUP_GROUP]);
+    } else {
+        collision_groups.set_whitelist(&[ROID_GROUP, POWERUP_GROUP]);
+    }
+
+    let collision_shape = ship.collision_shape();
+
+    // Put entry in collision world
+    let (collision_handle, obj) = collision_world.add(
+        Isometry2::new(Vector2::<f32>::new(x, y), heading),
+        collision_shape,
+        collision_groups,
+        GeometricQueryType::Contacts(0.0, 0.0),
+        0,
+    );
+
     // Create the entity
-    builder
+    let entity = builder
         .with(LinearVelocity(velocity))
         .with(AngularVelocity(0.0))
         .with(position)
         .with(rotation)
         .with(Wrapping)
-        .with(Ship::new(
-            length,
-            width,
-            mass,
-            thrust,
-            rotational_speed,
-            cannon,
-        ))
+        .with(CollisionHandle(collision_handle))
+        .with(ship)
+        .build();
+
+    // Annotate the collision object with the entity's ID
+    *obj.data_mut() = entity.id();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ship;
+    use crate::settings::Settings;
+    use ncollide2d::shape::{Ball, ConvexPolygon};
+
+    #[test]
+    fn collision_shape_is_hull() {
+        let ship = Ship::from_settings(&Settings::load().expect("settings"));
+        let shape = ship.collision_shape();
+        let polygon = shape
+            .as_shape::<ConvexPolygon<f32>>()
+            .expect("ship collision shape should be a polygon");
+        assert_eq!(polygon.points().len(), 3);
+    }
+
+    #[test]
+    fn flat_ship_collides_as_ball() {
+        let mut settings = Settings::load().expect("settings");
+        settings.ship_width = 0.0;
+        let ship = Ship::from_settings(&settings);
+        let shape = ship.collision_shape();
+        let ball = shape
+            .as_shape::<Ball<f32>>()
+            .expect("flat ship collision shape should be a ball");
+        assert_eq!(ball.radius, settings.ship_length / 2.0);
+
+        settings.ship_length = 0.0;
+        let ship = Ship::from_settings(&settings);
+        assert!(ship.collision_shape().as_shape::<Ball<f32>>().is_some());
+    }
 }
@@ -0,0 +1,138 @@
+use crate::settings::Settings;
+use specs::{Component, HashMapStorage};
+
+/// Fuel, weapon heat and energy for a ship.
+///
+/// Each resource is only limited if its capacity is greater than zero, so by default ships can
+/// thrust and fire as much as they like.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShipResources {
+    /// Fuel left. Thrusting burns fuel.
+    pub fuel: f32,
+    pub fuel_capacity: f32,
+    /// Weapon heat. Each shot adds heat, which dissipates over time; the cannon can't fire if
+    /// the shot would take it over capacity.
+    pub heat: f32,
+    pub heat_capacity: f32,
+    /// Energy left. Each shot uses energy, which recharges over time.
+    pub energy: f32,
+    pub energy_capacity: f32,
+}
+
+impl ShipResources {
+    /// A freshly launched ship: full of fuel and energy, with a cold cannon.
+    pub fn from_settings(settings: &Settings) -> ShipResources {
+        ShipResources {
+            fuel: settings.ship_fuel_capacity,
+            fuel_capacity: settings.ship_fuel_capacity,
+            heat: 0.0,
+            heat_capacity: settings.ship_heat_capacity,
+            energy: settings.ship_energy_capacity,
+            energy_capacity: settings.ship_energy_capacity,
+        }
+    }
+
+    /// Try to burn `amount` fuel, returning the fraction of it that was available.
+    pub fn burn(&mut self, amount: f32) -> f32 {
+        if self.fuel_capacity <= 0.0 || amount <= 0.0 {
+            return 1.0;
+        }
+        let burned = amount.min(self.fuel);
+        self.fuel -= burned;
+        burned / amount
+    }
+
+    /// Whether the cannon is cool enough and there's enough energy for another shot.
+    pub fn can_fire(&self, settings: &Settings) -> bool {
+        let cool_enough = self.heat_capacity <= 0.0
+            || self.heat + settings.ship_heat_per_shot <= self.heat_capacity;
+        let charged = self.energy_capacity <= 0.0 || self.energy >= settings.ship_energy_per_shot;
+        cool_enough && charged
+    }
+
+    /// Account for a shot being fired.
+    pub fn fire(&mut self, settings: &Settings) {
+        if self.heat_capacity > 0.0 {
+            self.heat += settings.ship_heat_per_shot;
+        }
+        if self.energy_capacity > 0.0 {
+            self.energy -= settings.ship_energy_per_shot;
+        }
+    }
+
+    /// Cool the cannon and recharge energy over `dt` seconds.
+    pub fn recover(&mut self, dt: f32, settings: &Settings) {
+        self.heat = (self.heat - settings.ship_cooling_rate * dt).max(0.0);
+        self.energy =
+            (self.energy + settings.ship_energy_recharge_rate * dt).min(self.energy_capacity);
+    }
+}
+
+impl Component for ShipResources {
+    type Storage = HashMapStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> Settings {
+        let mut settings = Settings::load().expect("settings");
+        settings.ship_fuel_capacity = 10.0;
+        settings.ship_heat_capacity = 3.0;
+        settings.ship_heat_per_shot = 2.0;
+        settings.ship_cooling_rate = 1.0;
+        settings.ship_energy_capacity = 5.0;
+        settings.ship_energy_per_shot = 1.0;
+        settings.ship_energy_recharge_rate = 2.0;
+        settings
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let settings = Settings::load().expect("settings");
+        let mut resources = ShipResources::from_settings(&settings);
+
+        assert_eq!(resources.burn(100.0), 1.0);
+        for _ in 0..100 {
+            assert!(resources.can_fire(&settings));
+            resources.fire(&settings);
+        }
+    }
+
+    #[test]
+    fn burning_runs_out_of_fuel() {
+        let mut resources = ShipResources::from_settings(&settings());
+
+        assert_eq!(resources.burn(8.0), 1.0);
+        assert_eq!(resources.burn(4.0), 0.5);
+        assert_eq!(resources.burn(1.0), 0.0);
+    }
+
+    #[test]
+    fn firing_heats_the_cannon_until_it_cools() {
+        let settings = settings();
+        let mut resources = ShipResources::from_settings(&settings);
+
+        resources.fire(&settings);
+        assert!(!resources.can_fire(&settings));
+
+        resources.recover(1.0, &settings);
+        assert!(resources.can_fire(&settings));
+    }
+
+    #[test]
+    fn firing_drains_energy() {
+        let mut settings = settings();
+        settings.ship_heat_capacity = 0.0;
+        let mut resources = ShipResources::from_settings(&settings);
+
+        for _ in 0..5 {
+            resources.fire(&settings);
+        }
+        assert!(!resources.can_fire(&settings));
+
+        resources.recover(10.0, &settings);
+        assert_eq!(resources.energy, 5.0);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::components::Pilot;
+use roid_rage_grpc::roid_rage as rpc;
+use specs::{Component, VecStorage};
+
+/// Running statistics for a single pilot.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub score: u32,
+    pub shots_fired: u32,
+    pub hits: u32,
+    pub deaths: u32,
+    /// Other pilots' ships destroyed by the pilot's bullets.
+    pub kills: u32,
+    /// Total seconds the pilot's ships have been alive.
+    pub survival_time: f32,
+    /// Ticks on which the pilot didn't answer before the command deadline.
+    pub deadline_misses: u32,
+}
+
+impl Stats {
+    /// Fraction of shots fired which hit something.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+
+    pub fn to_rpc(&self, pilot: &Pilot) -> rpc::PilotStats {
+        rpc::PilotStats {
+            pilot: pilot.url.clone(),
+            score: self.score,
+            lives: pilot.lives,
+            shots_fired: self.shots_fired,
+            hits: self.hits,
+            accuracy: self.accuracy(),
+            deaths: self.deaths,
+            survival_time: self.survival_time,
+            deadline_misses: self.deadline_misses,
+            name: pilot.name.clone(),
+            team: pilot.team.clone(),
+            kills: self.kills,
+        }
+    }
+}
+
+impl Component for Stats {
+    type Storage = VecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+
+    #[test]
+    fn accuracy_is_zero_without_shots() {
+        assert_eq!(Stats::default().accuracy(), 0.0);
+    }
+
+    #[test]
+    fn accuracy_is_hits_over_shots() {
+        let stats = Stats {
+            shots_fired: 4,
+            hits: 1,
+            ..Default::default()
+        };
+        assert!((stats.accuracy() - 0.25).abs() < 0.0001);
+    }
+}
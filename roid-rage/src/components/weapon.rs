@@ -0,0 +1,113 @@
+use crate::settings::Settings;
+use roid_rage_grpc::roid_rage as rpc;
+
+/// The weapons a ship's cannon can fire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weapon {
+    /// A single bullet.
+    Gun,
+    /// A fan of `spread_bullet_count` bullets.
+    Spread,
+    /// An instant hit on the first thing within `laser_range`.
+    Laser,
+    /// A stationary mine which arms itself after `mine_arm_delay` seconds.
+    Mine,
+    /// A missile which steers itself towards the nearest target.
+    Homing,
+}
+
+impl Weapon {
+    pub fn from_rpc(weapon: rpc::Weapon) -> Weapon {
+        match weapon {
+            rpc::Weapon::Gun => Weapon::Gun,
+            rpc::Weapon::Spread => Weapon::Spread,
+            rpc::Weapon::Laser => Weapon::Laser,
+            rpc::Weapon::Mine => Weapon::Mine,
+            rpc::Weapon::Homing => Weapon::Homing,
+        }
+    }
+
+    pub fn to_rpc(self) -> rpc::Weapon {
+        match self {
+            Weapon::Gun => rpc::Weapon::Gun,
+            Weapon::Spread => rpc::Weapon::Spread,
+            Weapon::Laser => rpc::Weapon::Laser,
+            Weapon::Mine => rpc::Weapon::Mine,
+            Weapon::Homing => rpc::Weapon::Homing,
+        }
+    }
+
+    #[cfg(feature = "render")]
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Gun => "gun",
+            Weapon::Spread => "spread",
+            Weapon::Laser => "laser",
+            Weapon::Mine => "mine",
+            Weapon::Homing => "homing",
+        }
+    }
+
+    /// Seconds between shots.
+    pub fn rate_of_fire(self, settings: &Settings) -> f32 {
+        match self {
+            Weapon::Gun => settings.rate_of_fire,
+            Weapon::Spread => settings.spread_rate_of_fire,
+            Weapon::Laser => settings.laser_rate_of_fire,
+            Weapon::Mine => settings.mine_rate_of_fire,
+            Weapon::Homing => settings.missile_rate_of_fire,
+        }
+    }
+
+    /// How fast the weapon's projectiles leave the ship. Lasers hit instantly and mines stay
+    /// put, so both are 0.
+    pub fn projectile_speed(self, settings: &Settings) -> f32 {
+        match self {
+            Weapon::Gun | Weapon::Spread => settings.bullet_speed,
+            Weapon::Laser | Weapon::Mine => 0.0,
+            Weapon::Homing => settings.missile_speed,
+        }
+    }
+
+    /// The directions, relative to the ship's heading, in which the weapon fires its projectiles.
+    pub fn spread(self, settings: &Settings) -> Vec<f32> {
+        match self {
+            Weapon::Spread if settings.spread_bullet_count > 1 => {
+                let count = settings.spread_bullet_count;
+                let step = settings.spread_angle / (count - 1) as f32;
+                (0..count)
+                    .map(|i| i as f32 * step - settings.spread_angle / 2.0)
+                    .collect()
+            }
+            _ => vec![0.0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spread_is_centred_on_the_heading() {
+        let mut settings = Settings::load().expect("settings");
+        settings.spread_bullet_count = 3;
+        settings.spread_angle = 0.5;
+
+        assert_eq!(Weapon::Spread.spread(&settings), vec![-0.25, 0.0, 0.25]);
+        assert_eq!(Weapon::Gun.spread(&settings), vec![0.0]);
+    }
+
+    #[test]
+    fn rpc_round_trip() {
+        for weapon in [
+            Weapon::Gun,
+            Weapon::Spread,
+            Weapon::Laser,
+            Weapon::Mine,
+            Weapon::Homing,
+        ] {
+            assert_eq!(Weapon::from_rpc(weapon.to_rpc()), weapon);
+        }
+    }
+}
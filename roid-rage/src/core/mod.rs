@@ -1,3 +1,2 @@
-pub mod collide;
-pub mod field;
+pub use sted::{collide, field};
 pub mod util;
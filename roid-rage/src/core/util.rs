@@ -1,9 +1,9 @@
 use glam::Vec2;
 use rand::Rng;
+use specs::Entity;
 use std::f32::consts::PI;
 
-pub fn random_bearing() -> f32 {
-    let mut rng = rand::rng();
+pub fn random_bearing<R: Rng + ?Sized>(rng: &mut R) -> f32 {
     (rng.random::<f32>() * 2.0 - 1.0) * PI
 }
 
@@ -12,10 +12,19 @@ pub fn from_quantity_and_bearing(quantity: f32, bearing: f32) -> Vec2 {
     Vec2::new(bearing.cos(), bearing.sin()) * quantity
 }
 
+/// A stable id for an entity, as sent to pilots.
+///
+/// The generation is in the upper 32 bits and the index in the lower 32 bits, so an id is never
+/// reused even when specs recycles the entity's index.
+pub fn entity_id(entity: Entity) -> u64 {
+    ((entity.gen().id() as u32 as u64) << 32) | entity.id() as u64
+}
+
 #[cfg(test)]
 mod tests {
-    use super::from_quantity_and_bearing;
+    use super::{entity_id, from_quantity_and_bearing};
     use float_cmp::approx_eq;
+    use specs::{Builder, World, WorldExt};
     use std::f32::consts::FRAC_PI_2;
 
     #[test]
@@ -31,4 +40,17 @@ mod tests {
         assert!(approx_eq!(f32, v.x, 0.0, epsilon = 0.0001));
         assert!(approx_eq!(f32, v.y, 2.0, epsilon = 0.0001));
     }
+
+    #[test]
+    fn entity_id_changes_when_index_is_reused() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        world.delete_entity(first).unwrap();
+        world.maintain();
+        let second = world.create_entity().build();
+
+        assert_eq!(first.id(), second.id());
+        assert_ne!(entity_id(first), entity_id(second));
+        assert_eq!(entity_id(second) & 0xffff_ffff, second.id() as u64);
+    }
 }
@@ -0,0 +1,370 @@
+use crate::components::{
+    Bullet, Fragment, Health, Invulnerable, LaserBeam, LinearVelocity, Mine, Pilot, Position,
+    PowerUp, Roid, Rotation, Ship, ShipResources, Stats,
+};
+use crate::game_mode::GameMode;
+use crate::rendering::{pilot_color, render_laser_beam, render_ship, Render};
+use crate::settings;
+use crate::simulation::Simulation;
+use ggez::event::EventHandler;
+use ggez::glam;
+use ggez::timer;
+use ggez::{graphics, Context, GameResult};
+use specs::prelude::*;
+use specs::Join;
+use sted::Velocity;
+
+type Point2 = glam::Vec2;
+
+pub(crate) struct Assets {
+    pub font: String,
+}
+
+impl Assets {
+    pub fn new(ctx: &mut Context) -> GameResult<Assets> {
+        let font_name = "DejaVuSansMono";
+        let font_data = graphics::FontData::from_path(ctx, "/DejaVuSansMono.ttf")?;
+        ctx.gfx.add_font(font_name, font_data);
+
+        Ok(Assets {
+            font: font_name.to_string(),
+        })
+    }
+}
+
+/// The windowed game: renders a `Simulation` and drives it from the ggez event loop.
+pub struct RoidRage {
+    simulation: Simulation,
+    assets: Assets,
+}
+
+impl RoidRage {
+    pub fn new(ctx: &mut Context, settings: settings::Settings) -> GameResult<RoidRage> {
+        let simulation = Simulation::new(settings)?;
+        let assets = Assets::new(ctx)?;
+
+        // Load/create resources such as images here.
+        Ok(RoidRage { simulation, assets })
+    }
+
+    pub fn simulation_mut(&mut self) -> &mut Simulation {
+        &mut self.simulation
+    }
+}
+
+impl EventHandler<ggez::GameError> for RoidRage {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // Always step by the same amount, however long the frame took, so that games are
+        // reproducible.
+        let time_step = self.simulation.time_step();
+        while ctx.time.check_update_time(self.simulation.tick_rate()) {
+            // Once the game is over, freeze it so the results stay put.
+            if !self.simulation.is_over() {
+                self.simulation.step(time_step);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let world = self.simulation.world();
+
+        // This adds a buffer around the edge of the screen so that roids don't teleport from one side to the next.
+        let settings = world.read_resource::<settings::Settings>();
+        let mut canvas = graphics::Canvas::from_frame(ctx, graphics::Color::BLACK);
+        canvas.set_screen_coordinates(graphics::Rect::new(
+            settings.maximum_roid_radius,
+            settings.maximum_roid_radius,
+            settings.screen_width - settings.maximum_roid_radius * 2.0,
+            settings.screen_height - settings.maximum_roid_radius * 2.0,
+        ));
+
+        for (position, rotation, roid) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<Rotation>(),
+            &world.read_storage::<Roid>(),
+        )
+            .join()
+        {
+            roid.render(position.0, rotation.0, ctx, &mut canvas)?;
+        }
+
+        for (position, bullet, _) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<Bullet>(),
+            !&world.read_storage::<Mine>(),
+        )
+            .join()
+        {
+            bullet.render(position.0, 0.0, ctx, &mut canvas)?;
+        }
+
+        for (position, mine) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<Mine>(),
+        )
+            .join()
+        {
+            mine.render(position.0, 0.0, ctx, &mut canvas)?;
+        }
+
+        for beam in (&world.read_storage::<LaserBeam>()).join() {
+            render_laser_beam(beam.start, beam.end, ctx, &mut canvas)?;
+        }
+
+        for (position, power_up) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<PowerUp>(),
+        )
+            .join()
+        {
+            power_up.render(position.0, 0.0, ctx, &mut canvas)?;
+        }
+
+        for (position, rotation, ship, pilot, invulnerable) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<Rotation>(),
+            &world.read_storage::<Ship>(),
+            (&world.read_storage::<Pilot>()).maybe(),
+            (&world.read_storage::<Invulnerable>()).maybe(),
+        )
+            .join()
+        {
+            let color = pilot_color(pilot.and_then(|p| p.color));
+            let invulnerable = invulnerable.map_or(0.0, |i| i.0);
+            render_ship(
+                ship,
+                color,
+                position.0,
+                rotation.0,
+                invulnerable,
+                ctx,
+                &mut canvas,
+            )?;
+        }
+
+        for (position, rotation, linear_velocity, ship, resources, health) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<Rotation>(),
+            &world.read_storage::<LinearVelocity>(),
+            &world.read_storage::<Ship>(),
+            (&world.read_storage::<ShipResources>()).maybe(),
+            (&world.read_storage::<Health>()).maybe(),
+        )
+            .join()
+        {
+            // HUD
+            let hud_font_size = 20.0;
+            let hud_x = settings.maximum_roid_radius + 10.0;
+            let hud_y = settings.maximum_roid_radius + 10.0;
+
+            let mut position_text =
+                graphics::Text::new(format!("position: {} {}", position.0.x, position.0.y));
+            position_text
+                .set_font(self.assets.font.clone())
+                .set_scale(hud_font_size);
+            canvas.draw(
+                &position_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(hud_x, hud_y))
+                    .color(graphics::Color::WHITE),
+            );
+
+            let hud_y = hud_y + hud_font_size;
+            let mut heading_text = graphics::Text::new(format!("heading: {}", rotation.0));
+            heading_text
+                .set_font(self.assets.font.clone())
+                .set_scale(hud_font_size);
+            canvas.draw(
+                &heading_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(hud_x, hud_y))
+                    .color(graphics::Color::WHITE),
+            );
+
+            let hud_y = hud_y + hud_font_size;
+            let mut bearing_text =
+                graphics::Text::new(format!("bearing: {}", linear_velocity.0.bearing()));
+            bearing_text
+                .set_font(self.assets.font.clone())
+                .set_scale(hud_font_size);
+            canvas.draw(
+                &bearing_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(hud_x, hud_y))
+                    .color(graphics::Color::WHITE),
+            );
+
+            let hud_y = hud_y + hud_font_size;
+            let mut speed_text =
+                graphics::Text::new(format!("speed: {}", linear_velocity.0.speed()));
+            speed_text
+                .set_font(self.assets.font.clone())
+                .set_scale(hud_font_size);
+            canvas.draw(
+                &speed_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(hud_x, hud_y))
+                    .color(graphics::Color::WHITE),
+            );
+
+            let hud_y = hud_y + hud_font_size;
+            let mut weapon_text =
+                graphics::Text::new(format!("weapon: {}", ship.cannon.weapon.name()));
+            weapon_text
+                .set_font(self.assets.font.clone())
+                .set_scale(hud_font_size);
+            canvas.draw(
+                &weapon_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(hud_x, hud_y))
+                    .color(graphics::Color::WHITE),
+            );
+
+            let hud_y = match health {
+                Some(health) => {
+                    let hud_y = hud_y + hud_font_size;
+                    let mut health_text = graphics::Text::new(format!(
+                        "hit points: {:.0}/{:.0}  shield: {:.0}/{:.0}",
+                        health.hit_points, health.max_hit_points, health.shield, health.max_shield
+                    ));
+                    health_text
+                        .set_font(self.assets.font.clone())
+                        .set_scale(hud_font_size);
+                    canvas.draw(
+                        &health_text,
+                        graphics::DrawParam::new()
+                            .dest(Point2::new(hud_x, hud_y))
+                            .color(graphics::Color::WHITE),
+                    );
+                    hud_y
+                }
+                None => hud_y,
+            };
+
+            // Only the resources which are limited are worth showing
+            let resources_text = resources.map_or_else(String::new, |resources| {
+                [
+                    ("fuel", resources.fuel, resources.fuel_capacity),
+                    ("heat", resources.heat, resources.heat_capacity),
+                    ("energy", resources.energy, resources.energy_capacity),
+                ]
+                .iter()
+                .filter(|(_, _, capacity)| *capacity > 0.0)
+                .map(|(name, value, capacity)| format!("{}: {:.0}/{:.0}", name, value, capacity))
+                .collect::<Vec<_>>()
+                .join("  ")
+            });
+            if !resources_text.is_empty() {
+                let hud_y = hud_y + hud_font_size;
+                let mut resources_text = graphics::Text::new(resources_text);
+                resources_text
+                    .set_font(self.assets.font.clone())
+                    .set_scale(hud_font_size);
+                canvas.draw(
+                    &resources_text,
+                    graphics::DrawParam::new()
+                        .dest(Point2::new(hud_x, hud_y))
+                        .color(graphics::Color::WHITE),
+                );
+            }
+        }
+
+        // Scoreboard
+        let hud_font_size = 20.0;
+        let hud_x = settings.maximum_roid_radius + 10.0;
+        let mut hud_y = settings.screen_height - settings.maximum_roid_radius - 10.0;
+        for (pilot, stats) in (
+            &world.read_storage::<Pilot>(),
+            &world.read_storage::<Stats>(),
+        )
+            .join()
+        {
+            hud_y -= hud_font_size;
+            let mut stats_text = graphics::Text::new(format!(
+                "{}  score: {}  lives: {}  shots: {}  hits: {}  accuracy: {:.0}%  deaths: {}  kills: {}  time: {:.1}s",
+                pilot.display_name(),
+                stats.score,
+                pilot.lives,
+                stats.shots_fired,
+                stats.hits,
+                stats.accuracy() * 100.0,
+                stats.deaths,
+                stats.kills,
+                stats.survival_time,
+            ));
+            stats_text
+                .set_font(self.assets.font.clone())
+                .set_scale(hud_font_size);
+            canvas.draw(
+                &stats_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(hud_x, hud_y))
+                    .color(pilot_color(pilot.color)),
+            );
+        }
+
+        for (position, fragment) in (
+            &world.read_storage::<Position>(),
+            &world.read_storage::<Fragment>(),
+        )
+            .join()
+        {
+            fragment.render(position.0, 0.0, ctx, &mut canvas)?;
+        }
+
+        // Game mode status, and the results once the game is over
+        let results = self.simulation.results();
+        if results.mode != GameMode::Endless {
+            let status = if results.mode == GameMode::TimeAttack {
+                format!(
+                    "{}  wave: {}  time left: {:.1}s",
+                    results.mode.name(),
+                    results.wave,
+                    (settings.time_attack_seconds - results.elapsed).max(0.0)
+                )
+            } else {
+                format!(
+                    "{}  wave: {}  time: {:.1}s",
+                    results.mode.name(),
+                    results.wave,
+                    results.elapsed
+                )
+            };
+            let mut status_text = graphics::Text::new(status);
+            status_text
+                .set_font(self.assets.font.clone())
+                .set_scale(20.0);
+            canvas.draw(
+                &status_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(
+                        settings.screen_width / 2.0,
+                        settings.maximum_roid_radius + 10.0,
+                    ))
+                    .color(graphics::Color::WHITE),
+            );
+        }
+        if results.outcome.is_some() {
+            let mut results_text = graphics::Text::new(results.to_string());
+            results_text
+                .set_font(self.assets.font.clone())
+                .set_scale(24.0);
+            canvas.draw(
+                &results_text,
+                graphics::DrawParam::new()
+                    .dest(Point2::new(
+                        settings.maximum_roid_radius + 40.0,
+                        settings.screen_height / 3.0,
+                    ))
+                    .color(graphics::Color::WHITE),
+            );
+        }
+
+        canvas.finish(ctx)?;
+
+        timer::yield_now();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! Game modes: how the roids come and when the game ends.
+use crate::settings::Settings;
+use roid_rage_grpc::roid_rage as rpc;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameMode {
+    /// The field is refilled with the same roids forever.
+    Endless,
+    /// Each wave of roids is bigger and faster than the last. Ends when every pilot is out of
+    /// lives.
+    Waves,
+    /// Pilots get a single life. Ends when every pilot has died.
+    Survival,
+    /// Score as much as possible in `time_attack_seconds`.
+    TimeAttack,
+}
+
+impl GameMode {
+    /// The mode chosen by the `game_mode` setting. Anything unrecognised is endless.
+    pub fn from_settings(settings: &Settings) -> GameMode {
+        match settings.game_mode.as_str() {
+            "waves" => GameMode::Waves,
+            "survival" => GameMode::Survival,
+            "time_attack" => GameMode::TimeAttack,
+            _ => GameMode::Endless,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Endless => "endless",
+            GameMode::Waves => "waves",
+            GameMode::Survival => "survival",
+            GameMode::TimeAttack => "time_attack",
+        }
+    }
+
+    /// The number of ships each pilot gets.
+    pub fn lives(&self, settings: &Settings) -> u32 {
+        match self {
+            GameMode::Survival => 1,
+            _ => settings.pilot_lives,
+        }
+    }
+
+    /// The roids to send in wave `wave`, counting from 1.
+    pub fn wave(&self, wave: u32, settings: &Settings) -> Wave {
+        match self {
+            GameMode::Waves => {
+                let later_waves = wave.saturating_sub(1);
+                Wave {
+                    roid_count: settings.initial_roid_count
+                        + later_waves * settings.wave_roid_increment,
+                    speed_factor: 1.0 + later_waves as f32 * settings.wave_speed_increase,
+                }
+            }
+            _ => Wave {
+                roid_count: settings.initial_roid_count,
+                speed_factor: 1.0,
+            },
+        }
+    }
+
+    /// Why the game is over, if it is.
+    ///
+    /// `pilots_out` is whether every pilot has played and run out of lives; `elapsed` is the
+    /// seconds of game time since the first pilot joined.
+    pub fn outcome(&self, pilots_out: bool, elapsed: f32, settings: &Settings) -> Option<Outcome> {
+        match self {
+            GameMode::Endless => None,
+            GameMode::TimeAttack if elapsed >= settings.time_attack_seconds => {
+                Some(Outcome::TimeUp)
+            }
+            _ if pilots_out => Some(Outcome::PilotsOut),
+            _ => None,
+        }
+    }
+}
+
+/// A wave of roids.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wave {
+    pub roid_count: u32,
+    /// How much faster than normal the roids move.
+    pub speed_factor: f32,
+}
+
+/// Why a game ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// Every pilot ran out of lives.
+    PilotsOut,
+    /// The time limit was reached.
+    TimeUp,
+}
+
+/// A summary of the game, with the pilots ranked by score.
+#[derive(Clone, Debug)]
+pub struct GameResults {
+    pub mode: GameMode,
+    /// Why the game ended, or `None` if it's still going.
+    pub outcome: Option<Outcome>,
+    /// The wave the game reached.
+    pub wave: u32,
+    /// Seconds of game time since the first pilot joined.
+    pub elapsed: f32,
+    pub pilots: Vec<rpc::PilotStats>,
+}
+
+impl GameResults {
+    pub fn new(
+        mode: GameMode,
+        outcome: Option<Outcome>,
+        wave: u32,
+        elapsed: f32,
+        mut pilots: Vec<rpc::PilotStats>,
+    ) -> GameResults {
+        pilots.sort_by_key(|p| std::cmp::Reverse(p.score));
+        GameResults {
+            mode,
+            outcome,
+            wave,
+            elapsed,
+            pilots,
+        }
+    }
+}
+
+impl fmt::Display for GameResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self.outcome {
+            Some(Outcome::PilotsOut) => "game over: every pilot is out",
+            Some(Outcome::TimeUp) => "game over: time's up",
+            None => "game in progress",
+        };
+        writeln!(
+            f,
+            "{} ({}) after {:.1}s, wave {}",
+            status,
+            self.mode.name(),
+            self.elapsed,
+            self.wave
+        )?;
+        for (rank, stats) in self.pilots.iter().enumerate() {
+            let name = if stats.name.is_empty() {
+                &stats.pilot
+            } else {
+                &stats.name
+            };
+            let team = if stats.team.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", stats.team)
+            };
+            writeln!(
+                f,
+                "{:>3}. {}{}  score: {}  kills: {}  deaths: {}  accuracy: {:.0}%  time: {:.1}s",
+                rank + 1,
+                name,
+                team,
+                stats.score,
+                stats.kills,
+                stats.deaths,
+                stats.accuracy * 100.0,
+                stats.survival_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(game_mode: &str) -> Settings {
+        let mut settings = Settings::load().expect("settings");
+        settings.game_mode = game_mode.to_string();
+        settings
+    }
+
+    #[test]
+    fn unknown_modes_are_endless() {
+        assert_eq!(
+            GameMode::from_settings(&settings("bogus")),
+            GameMode::Endless
+        );
+        assert_eq!(
+            GameMode::from_settings(&settings("time_attack")),
+            GameMode::TimeAttack
+        );
+    }
+
+    #[test]
+    fn waves_get_bigger_and_faster() {
+        let settings = settings("waves");
+        let mode = GameMode::from_settings(&settings);
+        let first = mode.wave(1, &settings);
+        let third = mode.wave(3, &settings);
+
+        assert_eq!(first.roid_count, settings.initial_roid_count);
+        assert_eq!(first.speed_factor, 1.0);
+        assert_eq!(
+            third.roid_count,
+            settings.initial_roid_count + 2 * settings.wave_roid_increment
+        );
+        assert!(third.speed_factor > first.speed_factor);
+    }
+
+    #[test]
+    fn end_conditions() {
+        let settings = settings("endless");
+        let limit = settings.time_attack_seconds;
+
+        assert_eq!(GameMode::Endless.outcome(true, limit, &settings), None);
+        assert_eq!(
+            GameMode::Survival.outcome(true, 0.0, &settings),
+            Some(Outcome::PilotsOut)
+        );
+        assert_eq!(GameMode::Waves.outcome(false, limit, &settings), None);
+        assert_eq!(
+            GameMode::TimeAttack.outcome(false, limit, &settings),
+            Some(Outcome::TimeUp)
+        );
+        assert_eq!(
+            GameMode::TimeAttack.outcome(false, limit - 1.0, &settings),
+            None
+        );
+    }
+
+    #[test]
+    fn results_rank_pilots_by_score() {
+        let pilot = |name: &str, score| rpc::PilotStats {
+            pilot: name.to_string(),
+            score,
+            ..Default::default()
+        };
+        let results = GameResults::new(
+            GameMode::Waves,
+            Some(Outcome::PilotsOut),
+            2,
+            30.0,
+            vec![pilot("a", 10), pilot("b", 30), pilot("c", 20)],
+        );
+        let ranking: Vec<_> = results.pilots.iter().map(|p| p.pilot.as_str()).collect();
+        assert_eq!(ranking, vec!["b", "c", "a"]);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::settings::Settings;
+use crate::simulation::Simulation;
+use std::time::Duration;
+
+/// Runs a `Simulation` without a window, advancing it by a fixed time delta on every tick.
+///
+/// Nothing here depends on ggez, so this works on machines with no display or GPU.
+pub struct HeadlessRunner {
+    simulation: Simulation,
+    time_delta: Duration,
+    ticks: u64,
+}
+
+impl HeadlessRunner {
+    /// Create a runner which steps at the game's normal tick rate.
+    pub fn new(settings: Settings) -> std::io::Result<HeadlessRunner> {
+        let simulation = Simulation::new(settings)?;
+        let time_delta = simulation.time_step();
+        Ok(HeadlessRunner {
+            simulation,
+            time_delta,
+            ticks: 0,
+        })
+    }
+
+    pub fn with_time_delta(
+        settings: Settings,
+        time_delta: Duration,
+    ) -> std::io::Result<HeadlessRunner> {
+        Ok(HeadlessRunner {
+            simulation: Simulation::new(settings)?,
+            time_delta,
+            ticks: 0,
+        })
+    }
+
+    /// Advance the simulation by a single tick.
+    pub fn step(&mut self) {
+        self.simulation.step(self.time_delta);
+        self.ticks += 1;
+    }
+
+    /// Advance the simulation by `ticks` ticks, or until the game is over.
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            if self.simulation.is_over() {
+                break;
+            }
+            self.step();
+        }
+    }
+
+    /// Advance the simulation until the game is over. In endless mode this never returns.
+    pub fn run_to_end(&mut self) {
+        while !self.simulation.is_over() {
+            self.step();
+        }
+    }
+
+    /// The number of ticks run so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn time_delta(&self) -> Duration {
+        self.time_delta
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    pub fn simulation_mut(&mut self) -> &mut Simulation {
+        &mut self.simulation
+    }
+}
@@ -1,27 +1,146 @@
-use ggez::conf;
-use ggez::event;
-use ggez::{ContextBuilder, GameResult};
-use std::path;
+use roid_rage::settings::Settings;
+use roid_rage::HeadlessRunner;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
 
-fn main() -> GameResult {
-    let settings =
-        roid_rage::settings::Settings::load().expect("Unable to load Roid Rage settings!");
+#[derive(StructOpt, Debug)]
+#[structopt(name = "roid-rage")]
+struct Opt {
+    /// Run the simulation without opening a window
+    #[structopt(long)]
+    headless: bool,
 
-    // Make a Context.
-    let (mut ctx, event_loop) = ContextBuilder::new("Roid Rage!", "Austin Bingham")
-        .window_setup(conf::WindowSetup::default().title("Roid Rage!"))
+    /// Stop after this many ticks (headless mode only). Otherwise runs until the game is over,
+    /// which in endless mode is forever.
+    #[structopt(long)]
+    ticks: Option<u64>,
+
+    /// Record the game to this replay file
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Play back a recorded game
+    Replay {
+        /// The replay file to play
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let opt = Opt::from_args();
+    let settings = Settings::load().expect("Unable to load Roid Rage settings!");
+
+    if let Some(Command::Replay { file }) = opt.command {
+        run_replay(settings, &file)
+    } else if opt.headless {
+        run_headless(settings, opt.ticks, opt.record.as_deref())
+    } else {
+        run_windowed(settings, opt.record.as_deref())
+    }
+}
+
+fn run_headless(
+    settings: Settings,
+    ticks: Option<u64>,
+    record: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut runner = HeadlessRunner::new(settings)?;
+    println!("Simulation seed: {}", runner.simulation().seed());
+    if let Some(path) = record {
+        runner.simulation_mut().record_to(path)?;
+    }
+
+    match ticks {
+        Some(ticks) => runner.run(ticks),
+        None => runner.run_to_end(),
+    }
+
+    println!("Headless run finished after {} ticks", runner.ticks());
+    print!("{}", runner.simulation().results());
+    Ok(())
+}
+
+#[cfg(feature = "render")]
+fn make_context(
+    title: &str,
+    width: f32,
+    height: f32,
+    settings: &Settings,
+) -> (ggez::Context, ggez::event::EventLoop<()>) {
+    use ggez::conf;
+    use ggez::ContextBuilder;
+
+    ContextBuilder::new("Roid Rage!", "Austin Bingham")
+        .window_setup(conf::WindowSetup::default().title(title))
         .window_mode(conf::WindowMode::default().dimensions(
-            settings.screen_width + settings.maximum_roid_radius * 2.0,
-            settings.screen_height + settings.maximum_roid_radius * 2.0,
+            width + settings.maximum_roid_radius * 2.0,
+            height + settings.maximum_roid_radius * 2.0,
         ))
-        .add_resource_path(path::PathBuf::from("./resources"))
+        .add_resource_path(PathBuf::from("./resources"))
         .build()
-        .expect("Ermahgerd, could not create ggez context!");
+        .expect("Ermahgerd, could not create ggez context!")
+}
+
+#[cfg(feature = "render")]
+fn run_windowed(
+    settings: Settings,
+    record: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use ggez::event;
+
+    // Make a Context.
+    let (mut ctx, event_loop) = make_context(
+        "Roid Rage!",
+        settings.screen_width,
+        settings.screen_height,
+        &settings,
+    );
 
     // Create an instance of your event handler. Usually, you should provide it with the Context object to use when
     // setting your game up.
-    let my_game = roid_rage::RoidRage::new(&mut ctx, settings)?;
+    let mut my_game = roid_rage::RoidRage::new(&mut ctx, settings)?;
+    println!("Simulation seed: {}", my_game.simulation_mut().seed());
+    if let Some(path) = record {
+        my_game.simulation_mut().record_to(path)?;
+    }
 
     // Run!
     event::run(ctx, event_loop, my_game)
 }
+
+#[cfg(feature = "render")]
+fn run_replay(settings: Settings, file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use ggez::event;
+    use roid_rage::replay::Replay;
+
+    let replay = Replay::load(file)?;
+    let (width, height) = match &replay.header.field {
+        Some(field) => (field.width as f32, field.height as f32),
+        None => (settings.screen_width, settings.screen_height),
+    };
+
+    let (mut ctx, event_loop) = make_context("Roid Rage! (replay)", width, height, &settings);
+    let viewer = roid_rage::ReplayViewer::new(&mut ctx, replay, settings)?;
+
+    event::run(ctx, event_loop, viewer)
+}
+
+#[cfg(not(feature = "render"))]
+fn run_windowed(
+    _settings: Settings,
+    _record: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("roid-rage was built without the `render` feature; use --headless".into())
+}
+
+#[cfg(not(feature = "render"))]
+fn run_replay(_settings: Settings, _file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    Err("roid-rage was built without the `render` feature, so replays can't be played".into())
+}
@@ -0,0 +1,124 @@
+use roid_rage_grpc::roid_rage as rpc;
+use roid_rage_grpc::roid_rage::pilot_message::Message;
+use roid_rage_grpc::roid_rage::pilot_stream_client::PilotStreamClient;
+use std::time::Instant;
+use tokio::runtime::Runtime;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::WatchStream;
+use tokio_stream::StreamExt;
+use tonic::Streaming;
+
+/// A long-lived streaming connection to a pilot process.
+///
+/// Game states are pushed to the pilot without waiting for an answer, and commands come back
+/// asynchronously. Only the newest of each is kept: a slow pilot skips stale states rather than
+/// falling further and further behind, and the game applies whatever command arrived last.
+pub struct PilotConnection {
+    states: watch::Sender<Option<rpc::GameState>>,
+    commands: watch::Receiver<Option<rpc::Command>>,
+    task: JoinHandle<()>,
+}
+
+impl PilotConnection {
+    /// Open a connection to the pilot at `url`, running it on `runtime`.
+    pub fn connect(runtime: &Runtime, url: &str) -> PilotConnection {
+        let (state_tx, state_rx) = watch::channel(None);
+        let (command_tx, command_rx) = watch::channel(None);
+
+        let url = url.to_string();
+        let task = runtime.spawn(async move {
+            if let Err(err) = play(url.clone(), state_rx, command_tx).await {
+                println!("Error communicating with pilot {}: {}", url, err);
+            }
+        });
+
+        PilotConnection {
+            states: state_tx,
+            commands: command_rx,
+            task,
+        }
+    }
+
+    /// Take over a connection the pilot opened to us, reading its commands from `inbound`.
+    ///
+    /// Returns the game states to stream back to the pilot. The stream ends when the connection
+    /// is dropped. This must be called from within a tokio runtime.
+    pub fn accept(
+        inbound: Streaming<rpc::PilotMessage>,
+    ) -> (PilotConnection, WatchStream<Option<rpc::GameState>>) {
+        let (state_tx, state_rx) = watch::channel(None);
+        let (command_tx, command_rx) = watch::channel(None);
+
+        let task = tokio::spawn(async move {
+            if let Err(err) = receive_commands(inbound, command_tx).await {
+                println!("Error communicating with pilot: {}", err);
+            }
+        });
+
+        let connection = PilotConnection {
+            states: state_tx,
+            commands: command_rx,
+            task,
+        };
+        (connection, WatchStream::from_changes(state_rx))
+    }
+
+    /// Send the current game state to the pilot. This never blocks.
+    pub fn send(&self, game_state: rpc::GameState) {
+        self.states.send_replace(Some(game_state));
+    }
+
+    /// Wait until `deadline` for a command the pilot has sent since the last call.
+    ///
+    /// If several commands arrived in the meantime only the newest is returned. Returns `None` if
+    /// the deadline passes or the connection closes first.
+    pub async fn next_command(&mut self, deadline: Instant) -> Option<rpc::Command> {
+        let deadline = tokio::time::Instant::from_std(deadline);
+        match tokio::time::timeout_at(deadline, self.commands.changed()).await {
+            Ok(Ok(())) => *self.commands.borrow_and_update(),
+            _ => None,
+        }
+    }
+
+    /// Whether the connection to the pilot has been lost.
+    pub fn is_closed(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for PilotConnection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn play(
+    url: String,
+    states: watch::Receiver<Option<rpc::GameState>>,
+    commands: watch::Sender<Option<rpc::Command>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut client = PilotStreamClient::connect(url).await?;
+
+    let outbound = WatchStream::from_changes(states).filter_map(|state| state);
+    let mut inbound = client.play(outbound).await?.into_inner();
+
+    while let Some(command) = inbound.message().await? {
+        commands.send_replace(Some(command));
+    }
+
+    Ok(())
+}
+
+async fn receive_commands(
+    mut inbound: Streaming<rpc::PilotMessage>,
+    commands: watch::Sender<Option<rpc::Command>>,
+) -> Result<(), tonic::Status> {
+    while let Some(message) = inbound.message().await? {
+        if let Some(Message::Command(command)) = message.message {
+            commands.send_replace(Some(command));
+        }
+    }
+
+    Ok(())
+}
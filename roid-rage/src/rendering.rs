@@ -2,11 +2,11 @@ use ggez::glam::Vec2 as GgezVec2;
 use ggez::graphics::{self, Canvas, Color, DrawMode, DrawParam, StrokeOptions};
 use ggez::{Context, GameResult};
 use glam::Vec2 as GameVec2;
-use std::f32::consts::PI;
 
 type Point2 = GgezVec2;
 
-use crate::components::{Bullet, Fragment, Roid, Ship};
+use crate::components::{Bullet, Fragment, Mine, PowerUp, Roid, Ship};
+use roid_rage_grpc::roid_rage as rpc;
 
 pub trait Render {
     fn render(
@@ -26,30 +26,36 @@ impl Render for Roid {
         ctx: &mut Context,
         canvas: &mut Canvas,
     ) -> GameResult<()> {
-        let angle_step = (PI * 2.0) / self.points.len() as f32;
-        let line_points: Vec<Point2> = self
-            .points
-            .iter()
-            .enumerate()
-            .map(|(i, distance)| {
-                let angle = angle_step * i as f32;
-                Point2::new(angle.cos(), angle.sin()) * *distance
-            })
-            .collect();
-
-        let mb = &mut graphics::MeshBuilder::new();
-        mb.polygon(
+        draw_outline(
+            &self.outline(),
             DrawMode::Stroke(StrokeOptions::DEFAULT),
-            &line_points,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        )?;
-
-        let mesh = graphics::Mesh::from_data(ctx, mb.build());
-        let param = DrawParam::new()
-            .rotation(direction)
-            .dest(to_ggez_vec2(position));
-        canvas.draw(&mesh, param);
-        Ok(())
+            Color::WHITE,
+            position,
+            direction,
+            ctx,
+            canvas,
+        )
+    }
+}
+
+impl Render for rpc::Roid {
+    fn render(
+        &self,
+        position: GameVec2,
+        direction: f32,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult<()> {
+        let outline: Vec<GameVec2> = self.outline.iter().map(GameVec2::from).collect();
+        draw_outline(
+            &outline,
+            DrawMode::Stroke(StrokeOptions::DEFAULT),
+            Color::WHITE,
+            position,
+            direction,
+            ctx,
+            canvas,
+        )
     }
 }
 
@@ -61,20 +67,53 @@ impl Render for Bullet {
         ctx: &mut Context,
         canvas: &mut Canvas,
     ) -> GameResult<()> {
-        let mb = &mut graphics::MeshBuilder::new();
-        mb.circle(
-            DrawMode::fill(),
-            to_ggez_vec2(position),
-            Bullet::radius(),
-            0.1,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        )?;
-        let mesh = graphics::Mesh::from_data(ctx, mb.build());
-        canvas.draw(&mesh, DrawParam::new());
-        Ok(())
+        draw_dot(position, Bullet::radius(), Color::WHITE, ctx, canvas)
+    }
+}
+
+impl Render for rpc::Bullet {
+    fn render(
+        &self,
+        position: GameVec2,
+        _direction: f32,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult<()> {
+        if self.weapon == rpc::Weapon::Mine as i32 {
+            render_mine(position, true, ctx, canvas)
+        } else {
+            draw_dot(position, Bullet::radius(), Color::WHITE, ctx, canvas)
+        }
     }
 }
 
+impl Render for Mine {
+    fn render(
+        &self,
+        position: GameVec2,
+        _direction: f32,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult<()> {
+        render_mine(position, self.is_armed(), ctx, canvas)
+    }
+}
+
+/// Draw a mine. Mines which aren't armed yet are drawn faintly.
+pub fn render_mine(
+    position: GameVec2,
+    armed: bool,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+) -> GameResult<()> {
+    let color = if armed {
+        Color::WHITE
+    } else {
+        Color::new(1.0, 1.0, 1.0, 0.4)
+    };
+    draw_ring(position, Mine::radius(), color, ctx, canvas)
+}
+
 impl Render for Fragment {
     fn render(
         &self,
@@ -83,17 +122,56 @@ impl Render for Fragment {
         ctx: &mut Context,
         canvas: &mut Canvas,
     ) -> GameResult<()> {
-        let mb = &mut graphics::MeshBuilder::new();
-        mb.circle(
-            DrawMode::fill(),
-            to_ggez_vec2(position),
-            Fragment::radius(),
-            0.1,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        )?;
-        let mesh = graphics::Mesh::from_data(ctx, mb.build());
-        canvas.draw(&mesh, DrawParam::new());
-        Ok(())
+        draw_dot(position, Fragment::radius(), Color::WHITE, ctx, canvas)
+    }
+}
+
+impl Render for rpc::Fragment {
+    fn render(
+        &self,
+        position: GameVec2,
+        _direction: f32,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult<()> {
+        draw_dot(position, Fragment::radius(), Color::WHITE, ctx, canvas)
+    }
+}
+
+impl Render for PowerUp {
+    fn render(
+        &self,
+        position: GameVec2,
+        _direction: f32,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult<()> {
+        let color = power_up_color(self.kind.to_rpc());
+        draw_dot(position, PowerUp::radius(), color, ctx, canvas)
+    }
+}
+
+impl Render for rpc::PowerUp {
+    fn render(
+        &self,
+        position: GameVec2,
+        _direction: f32,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult<()> {
+        let color = power_up_color(self.kind());
+        draw_dot(position, self.radius, color, ctx, canvas)
+    }
+}
+
+/// Each kind of power-up has its own colour so players can tell them apart.
+fn power_up_color(kind: rpc::PowerUpKind) -> Color {
+    match kind {
+        rpc::PowerUpKind::RapidFire => Color::RED,
+        rpc::PowerUpKind::SpreadShot => Color::YELLOW,
+        rpc::PowerUpKind::Shield => Color::CYAN,
+        rpc::PowerUpKind::ExtraLife => Color::GREEN,
+        rpc::PowerUpKind::FuelRefill => Color::MAGENTA,
     }
 }
 
@@ -105,26 +183,118 @@ impl Render for Ship {
         ctx: &mut Context,
         canvas: &mut Canvas,
     ) -> GameResult<()> {
-        let mb = &mut graphics::MeshBuilder::new();
-        // let center = Point2::new(0.0, 0.0);
-        let points = vec![
-            Point2::new(self.length / 2.0, 0.0),
-            Point2::new(-1.0 * self.length / 2.0, -1.0 * self.width / 2.0),
-            Point2::new(-1.0 * self.length / 2.0, self.width / 2.0),
-        ];
-
-        mb.polygon(
-            DrawMode::stroke(1.0),
-            &points,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        )?;
-        let mesh = graphics::Mesh::from_data(ctx, mb.build());
-        let param = DrawParam::new()
-            .rotation(direction)
-            .dest(to_ggez_vec2(position));
-        canvas.draw(&mesh, param);
-        Ok(())
+        render_ship(self, Color::WHITE, position, direction, 0.0, ctx, canvas)
+    }
+}
+
+/// How many times a second an invulnerable ship blinks.
+const BLINK_RATE: f32 = 4.0;
+
+/// Draw a ship in its pilot's colour, blinking while it has `invulnerable` seconds of
+/// invulnerability left.
+pub fn render_ship(
+    ship: &Ship,
+    color: Color,
+    position: GameVec2,
+    direction: f32,
+    invulnerable: f32,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+) -> GameResult<()> {
+    if invulnerable > 0.0 && (invulnerable * BLINK_RATE * 2.0) as u32 % 2 == 1 {
+        return Ok(());
     }
+
+    draw_outline(
+        &ship.hull(),
+        DrawMode::stroke(1.0),
+        color,
+        position,
+        direction,
+        ctx,
+        canvas,
+    )
+}
+
+/// Draw the beam of a laser shot from `start` to `end`.
+pub fn render_laser_beam(
+    start: GameVec2,
+    end: GameVec2,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+) -> GameResult<()> {
+    let mesh = graphics::Mesh::new_line(
+        ctx,
+        &[to_ggez_vec2(start), to_ggez_vec2(end)],
+        2.0,
+        Color::RED,
+    )?;
+    canvas.draw(&mesh, DrawParam::new());
+    Ok(())
+}
+
+/// The colour a pilot asked for, or white if it didn't.
+pub fn pilot_color(color: Option<rpc::Color>) -> Color {
+    color.map_or(Color::WHITE, |c| Color::new(c.r, c.g, c.b, 1.0))
+}
+
+/// Draw a closed polygon, given relative to `position`, rotated by `direction`.
+fn draw_outline(
+    points: &[GameVec2],
+    mode: DrawMode,
+    color: Color,
+    position: GameVec2,
+    direction: f32,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+) -> GameResult<()> {
+    let points: Vec<Point2> = points.iter().map(|p| to_ggez_vec2(*p)).collect();
+
+    let mb = &mut graphics::MeshBuilder::new();
+    mb.polygon(mode, &points, color)?;
+
+    let mesh = graphics::Mesh::from_data(ctx, mb.build());
+    let param = DrawParam::new()
+        .rotation(direction)
+        .dest(to_ggez_vec2(position));
+    canvas.draw(&mesh, param);
+    Ok(())
+}
+
+/// Draw a small filled circle.
+fn draw_dot(
+    position: GameVec2,
+    radius: f32,
+    color: Color,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+) -> GameResult<()> {
+    let mb = &mut graphics::MeshBuilder::new();
+    mb.circle(DrawMode::fill(), to_ggez_vec2(position), radius, 0.1, color)?;
+    let mesh = graphics::Mesh::from_data(ctx, mb.build());
+    canvas.draw(&mesh, DrawParam::new());
+    Ok(())
+}
+
+/// Draw the outline of a small circle.
+fn draw_ring(
+    position: GameVec2,
+    radius: f32,
+    color: Color,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+) -> GameResult<()> {
+    let mb = &mut graphics::MeshBuilder::new();
+    mb.circle(
+        DrawMode::Stroke(StrokeOptions::DEFAULT),
+        to_ggez_vec2(position),
+        radius,
+        0.1,
+        color,
+    )?;
+    let mesh = graphics::Mesh::from_data(ctx, mb.build());
+    canvas.draw(&mesh, DrawParam::new());
+    Ok(())
 }
 
 fn to_ggez_vec2(value: GameVec2) -> GgezVec2 {
@@ -0,0 +1,151 @@
+//! Recording games to, and loading them from, replay files.
+//!
+//! A replay is a `ReplayHeader` followed by a `ReplayFrame` for every tick, each written as a
+//! length-delimited protobuf message. Frames hold the whole game state plus the command each
+//! pilot gave, so a replay can be watched back to see why a pilot did what it did.
+use prost::Message;
+use roid_rage_grpc::roid_rage as rpc;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Writes a game to a replay, one frame per tick.
+pub struct ReplayRecorder<W = BufWriter<File>> {
+    writer: W,
+    tick: u64,
+}
+
+impl ReplayRecorder {
+    /// Create a replay file at `path`, overwriting any existing file.
+    pub fn create(path: &Path, header: &rpc::ReplayHeader) -> io::Result<ReplayRecorder> {
+        ReplayRecorder::new(BufWriter::new(File::create(path)?), header)
+    }
+}
+
+impl<W: Write> ReplayRecorder<W> {
+    pub fn new(mut writer: W, header: &rpc::ReplayHeader) -> io::Result<ReplayRecorder<W>> {
+        writer.write_all(&header.encode_length_delimited_to_vec())?;
+        Ok(ReplayRecorder { writer, tick: 0 })
+    }
+
+    /// Record the next tick.
+    ///
+    /// Every frame is flushed straight away so the replay is usable even if the game is killed.
+    pub fn record(
+        &mut self,
+        state: rpc::GameState,
+        commands: Vec<rpc::PilotCommand>,
+        stats: Vec<rpc::PilotStats>,
+        ship_colors: Vec<rpc::ShipColor>,
+        armed_mines: Vec<u64>,
+    ) -> io::Result<()> {
+        let frame = rpc::ReplayFrame {
+            tick: self.tick,
+            state: Some(state),
+            commands,
+            stats,
+            ship_colors,
+            armed_mines,
+        };
+        self.writer
+            .write_all(&frame.encode_length_delimited_to_vec())?;
+        self.writer.flush()?;
+        self.tick += 1;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A recorded game.
+pub struct Replay {
+    pub header: rpc::ReplayHeader,
+    pub frames: Vec<rpc::ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Replay::read(File::open(path)?)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Replay> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut data = &data[..];
+
+        let header = rpc::ReplayHeader::decode_length_delimited(&mut data).map_err(invalid_data)?;
+        let mut frames = Vec::new();
+        while !data.is_empty() {
+            frames
+                .push(rpc::ReplayFrame::decode_length_delimited(&mut data).map_err(invalid_data)?);
+        }
+
+        Ok(Replay { header, frames })
+    }
+}
+
+fn invalid_data(err: prost::DecodeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> rpc::ReplayHeader {
+        rpc::ReplayHeader {
+            seed: 42,
+            tick_rate: 60,
+            field: Some(rpc::Field {
+                width: 800,
+                height: 600,
+            }),
+        }
+    }
+
+    #[test]
+    fn recorded_frames_can_be_read_back() {
+        let mut recorder = ReplayRecorder::new(Vec::new(), &header()).unwrap();
+        for _ in 0..3 {
+            recorder
+                .record(
+                    rpc::GameState::default(),
+                    vec![rpc::PilotCommand {
+                        ship: 7,
+                        command: Some(rpc::Command::null()),
+                    }],
+                    Vec::new(),
+                    vec![rpc::ShipColor {
+                        ship: 7,
+                        color: Some(rpc::Color {
+                            r: 1.0,
+                            g: 0.5,
+                            b: 0.0,
+                        }),
+                    }],
+                    vec![9],
+                )
+                .unwrap();
+        }
+
+        let replay = Replay::read(&recorder.into_inner()[..]).unwrap();
+
+        assert_eq!(replay.header, header());
+        assert_eq!(replay.frames.len(), 3);
+        assert_eq!(replay.frames[2].tick, 2);
+        assert_eq!(replay.frames[0].commands[0].ship, 7);
+        assert_eq!(replay.frames[1].ship_colors[0].color.unwrap().g, 0.5);
+        assert_eq!(replay.frames[1].armed_mines, vec![9]);
+    }
+
+    #[test]
+    fn truncated_replay_is_an_error() {
+        let recorder = ReplayRecorder::new(Vec::new(), &header()).unwrap();
+        let mut data = recorder.into_inner();
+        data.pop();
+
+        assert!(Replay::read(&data[..]).is_err());
+    }
+}
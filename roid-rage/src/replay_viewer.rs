@@ -0,0 +1,295 @@
+use crate::components::{Cannon, Ship, Weapon};
+use crate::game::Assets;
+use crate::rendering::{pilot_color, render_laser_beam, render_mine, render_ship, Render};
+use crate::replay::Replay;
+use crate::settings::Settings;
+use ggez::event::EventHandler;
+use ggez::glam;
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::timer;
+use ggez::{graphics, Context, GameResult};
+use roid_rage_grpc::roid_rage as rpc;
+use std::convert::TryFrom;
+
+type Point2 = glam::Vec2;
+
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 16.0;
+
+/// Seconds skipped by page up/down.
+const SEEK_SECONDS: f64 = 5.0;
+
+/// Plays back a recorded game with the ggez renderer.
+///
+/// Controls:
+/// * Space - pause/resume
+/// * Left/right arrow - step one tick back/forward (pauses playback)
+/// * Up/down arrow - double/halve playback speed
+/// * Page up/page down - seek backward/forward five seconds
+/// * Home/End - jump to the start/end
+pub struct ReplayViewer {
+    replay: Replay,
+    settings: Settings,
+    assets: Assets,
+    /// Index of the frame being shown. This is fractional so that we can play back slower
+    /// than one frame per tick.
+    position: f64,
+    speed: f64,
+    paused: bool,
+}
+
+impl ReplayViewer {
+    pub fn new(ctx: &mut Context, replay: Replay, settings: Settings) -> GameResult<ReplayViewer> {
+        let assets = Assets::new(ctx)?;
+        Ok(ReplayViewer {
+            replay,
+            settings,
+            assets,
+            position: 0.0,
+            speed: 1.0,
+            paused: false,
+        })
+    }
+
+    fn tick_rate(&self) -> u32 {
+        self.replay.header.tick_rate.max(1)
+    }
+
+    fn last_frame(&self) -> f64 {
+        self.replay.frames.len().saturating_sub(1) as f64
+    }
+
+    fn seek(&mut self, frames: f64) {
+        self.position = (self.position + frames).max(0.0).min(self.last_frame());
+    }
+
+    fn frame(&self) -> Option<&rpc::ReplayFrame> {
+        self.replay.frames.get(self.position as usize)
+    }
+
+    fn draw_text(&self, canvas: &mut graphics::Canvas, text: String, x: f32, y: f32) {
+        let mut text = graphics::Text::new(text);
+        text.set_font(self.assets.font.clone()).set_scale(20.0);
+        canvas.draw(
+            &text,
+            graphics::DrawParam::new()
+                .dest(Point2::new(x, y))
+                .color(graphics::Color::WHITE),
+        );
+    }
+}
+
+impl EventHandler<ggez::GameError> for ReplayViewer {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        while ctx.time.check_update_time(self.tick_rate()) {
+            if !self.paused {
+                self.seek(self.speed);
+            }
+        }
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        let seek_frames = SEEK_SECONDS * self.tick_rate() as f64;
+        match input.keycode {
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            Some(KeyCode::Space) => self.paused = !self.paused,
+            Some(KeyCode::Right) => {
+                self.paused = true;
+                self.seek(1.0);
+            }
+            Some(KeyCode::Left) => {
+                self.paused = true;
+                self.seek(-1.0);
+            }
+            Some(KeyCode::Up) => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            Some(KeyCode::Down) => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            Some(KeyCode::PageDown) => self.seek(seek_frames),
+            Some(KeyCode::PageUp) => self.seek(-seek_frames),
+            Some(KeyCode::Home) => self.position = 0.0,
+            Some(KeyCode::End) => self.position = self.last_frame(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let margin = self.settings.maximum_roid_radius;
+        let (width, height) = match &self.replay.header.field {
+            Some(field) => (field.width as f32, field.height as f32),
+            None => (self.settings.screen_width, self.settings.screen_height),
+        };
+
+        let mut canvas = graphics::Canvas::from_frame(ctx, graphics::Color::BLACK);
+        canvas.set_screen_coordinates(graphics::Rect::new(
+            margin,
+            margin,
+            width - margin * 2.0,
+            height - margin * 2.0,
+        ));
+
+        let frame = match self.frame() {
+            Some(frame) => frame,
+            None => {
+                self.draw_text(
+                    &mut canvas,
+                    "empty replay".to_string(),
+                    margin + 10.0,
+                    margin + 10.0,
+                );
+                canvas.finish(ctx)?;
+                return Ok(());
+            }
+        };
+        let state = frame.state.clone().unwrap_or_default();
+
+        for roid in &state.roids {
+            roid.render(roid.position(), roid.rotation, ctx, &mut canvas)?;
+        }
+
+        for bullet in &state.bullets {
+            let position = bullet.position.as_ref().map(Into::into).unwrap_or_default();
+            if bullet.weapon == rpc::Weapon::Mine as i32 {
+                let armed = frame.armed_mines.contains(&bullet.id);
+                render_mine(position, armed, ctx, &mut canvas)?;
+            } else {
+                bullet.render(position, 0.0, ctx, &mut canvas)?;
+            }
+        }
+
+        for beam in &state.laser_beams {
+            let start = beam.start.as_ref().map(Into::into).unwrap_or_default();
+            let end = beam.end.as_ref().map(Into::into).unwrap_or_default();
+            render_laser_beam(start, end, ctx, &mut canvas)?;
+        }
+
+        for power_up in &state.power_ups {
+            let position = power_up
+                .position
+                .as_ref()
+                .map(Into::into)
+                .unwrap_or_default();
+            power_up.render(position, 0.0, ctx, &mut canvas)?;
+        }
+
+        // Ship dimensions aren't recorded, so they come from the current settings.
+        for ship in &state.other_ships {
+            let hull = Ship::new(
+                self.settings.ship_length,
+                self.settings.ship_width,
+                ship.mass,
+                ship.thrust,
+                0.0,
+                Cannon {
+                    bullet_speed: 0.0,
+                    rate_of_fire: 0.0,
+                    weapon: Weapon::Gun,
+                },
+            );
+            let color = frame
+                .ship_colors
+                .iter()
+                .find(|ship_color| ship_color.ship == ship.id)
+                .and_then(|ship_color| ship_color.color);
+            let invulnerable = ship.effects.map_or(0.0, |effects| effects.shield);
+            render_ship(
+                &hull,
+                pilot_color(color),
+                ship.position(),
+                ship.heading,
+                invulnerable,
+                ctx,
+                &mut canvas,
+            )?;
+        }
+
+        for fragment in &state.fragments {
+            let position = fragment
+                .position
+                .as_ref()
+                .map(Into::into)
+                .unwrap_or_default();
+            fragment.render(position, 0.0, ctx, &mut canvas)?;
+        }
+
+        // Playback status
+        let hud_font_size = 20.0;
+        let hud_x = margin + 10.0;
+        let mut hud_y = margin + 10.0;
+        self.draw_text(
+            &mut canvas,
+            format!(
+                "tick: {} / {}  speed: {}x{}",
+                frame.tick,
+                self.replay.frames.len(),
+                self.speed,
+                if self.paused { "  [paused]" } else { "" }
+            ),
+            hud_x,
+            hud_y,
+        );
+
+        // What each pilot told its ship to do
+        for command in &frame.commands {
+            hud_y += hud_font_size;
+            let cmd = command.command.unwrap_or_else(rpc::Command::null);
+            let thrust = cmd.thrust.unwrap_or(if cmd.thrusters { 1.0 } else { 0.0 });
+            let rotation = match (cmd.turn_rate, rpc::Rotation::try_from(cmd.rotation)) {
+                (Some(turn_rate), _) => format!("{:.2}", turn_rate),
+                (None, Ok(rpc::Rotation::Clockwise)) => "cw".to_string(),
+                (None, Ok(rpc::Rotation::Counterclockwise)) => "ccw".to_string(),
+                _ => "-".to_string(),
+            };
+            let weapon = cmd
+                .weapon
+                .and_then(|weapon| rpc::Weapon::try_from(weapon).ok())
+                .map_or("-", |weapon| Weapon::from_rpc(weapon).name());
+            self.draw_text(
+                &mut canvas,
+                format!(
+                    "ship {}  fire: {}  thrust: {:.2}  reverse: {:.2}  strafe: {:.2}  rotation: {}  weapon: {}",
+                    command.ship,
+                    cmd.fire,
+                    thrust,
+                    cmd.reverse_thrust.unwrap_or(0.0),
+                    cmd.strafe.unwrap_or(0.0),
+                    rotation,
+                    weapon
+                ),
+                hud_x,
+                hud_y,
+            );
+        }
+
+        // Scoreboard
+        let mut hud_y = height - margin - 10.0;
+        for stats in &frame.stats {
+            hud_y -= hud_font_size;
+            let name = if stats.name.is_empty() {
+                &stats.pilot
+            } else {
+                &stats.name
+            };
+            self.draw_text(
+                &mut canvas,
+                format!(
+                    "{}  score: {}  lives: {}  deaths: {}",
+                    name, stats.score, stats.lives, stats.deaths
+                ),
+                hud_x,
+                hud_y,
+            );
+        }
+
+        canvas.finish(ctx)?;
+
+        timer::yield_now();
+
+        Ok(())
+    }
+}
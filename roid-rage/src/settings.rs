@@ -53,6 +53,18 @@ initialize_settings!(
     (roid_bumpiness, f32, 0.1),
     (rate_of_fire, f32, 0.5),
     (bullet_speed, f32, 1000.0),
+    (spread_rate_of_fire, f32, 0.8),
+    (spread_bullet_count, u32, 5),
+    (spread_angle, f32, 0.6),
+    (laser_rate_of_fire, f32, 1.0),
+    (laser_range, f32, 300.0),
+    (mine_rate_of_fire, f32, 2.0),
+    (mine_arm_delay, f32, 1.0),
+    (mine_lifetime, f32, 20.0),
+    (missile_rate_of_fire, f32, 1.5),
+    (missile_speed, f32, 300.0),
+    (missile_turn_rate, f32, 3.0),
+    (missile_lifetime, f32, 5.0),
     (min_initial_roid_speed, f32, 50.0),
     (max_initial_roid_speed, f32, 100.0),
     (initial_roid_count, u32, 10),
@@ -61,6 +73,48 @@ initialize_settings!(
     (ship_mass, f32, 1.0),
     (ship_thrust, f32, 300.0),
     (ship_rotational_speed, f32, 6.0),
+    (ship_reverse_thrust, f32, 150.0),
+    (ship_strafe_thrust, f32, 100.0),
+    (ship_respawn_delay, f32, 2.0),
+    (ship_hit_points, f32, 100.0),
+    (ship_shield, f32, 50.0),
+    (ship_shield_recharge_rate, f32, 10.0),
+    (collision_damage, f32, 0.02),
+    (bullet_damage, f32, 50.0),
+    (respawn_invulnerability, f32, 2.0),
+    (ship_fuel_capacity, f32, 0.0),
+    (ship_fuel_burn_rate, f32, 1.0),
+    (ship_heat_capacity, f32, 0.0),
+    (ship_heat_per_shot, f32, 1.0),
+    (ship_cooling_rate, f32, 1.0),
+    (ship_energy_capacity, f32, 0.0),
+    (ship_energy_per_shot, f32, 1.0),
+    (ship_energy_recharge_rate, f32, 1.0),
+    (pilot_lives, u32, 3),
+    (large_roid_points, u32, 20),
+    (medium_roid_points, u32, 50),
+    (small_roid_points, u32, 100),
+    (power_up_drop_chance, f32, 0.1),
+    (power_up_lifetime, f32, 10.0),
+    (power_up_duration, f32, 10.0),
+    (spread_shot_angle, f32, 0.15),
+    (rapid_fire_drop_weight, f32, 1.0),
+    (spread_shot_drop_weight, f32, 1.0),
+    (shield_drop_weight, f32, 1.0),
+    (extra_life_drop_weight, f32, 0.5),
+    (fuel_refill_drop_weight, f32, 1.0),
+    (pvp, bool, false),
+    (friendly_fire, bool, false),
+    (ship_points, u32, 200),
+    (game_mode, String, "endless".to_string()),
+    (wave_roid_increment, u32, 2),
+    (wave_speed_increase, f32, 0.1),
+    (time_attack_seconds, f32, 120.0),
+    (pilot_command_deadline_ms, u64, 10),
+    (pilot_fallback_command, String, "repeat".to_string()),
+    (pilot_max_strikes, u32, 60),
+    (seed, u64, 0),
+    (tick_rate, u32, 60),
     (
         pilot_registration_url,
         SocketAddr,
@@ -0,0 +1,303 @@
+use crate::components::{DepartedPilots, GameProgress, GameRng, Pilot, Stats, TimeDelta};
+use crate::core::field::Field;
+use crate::game_mode::{GameMode, GameResults};
+use crate::replay::ReplayRecorder;
+use crate::settings::Settings;
+use crate::systems::{
+    AgeFragmentsSystem, AgePowerUpsSystem, ArmMinesSystem, CleanupCollisionsSystem,
+    CollectPowerUpsSystem, DetectCollisionsSystem, EndGameSystem, ExplodeBulletsSystem,
+    ExplodeRoidsSystem, ExplodeShipsSystem, FireWeaponsSystem, MoveObjectsSystem,
+    PilotRegistrationSystem, QueryPilotSystem, RechargeShieldsSystem, RemoveOutOfBoundsSystem,
+    RepopulateSystem, RespawnShipSystem, SteerMissilesSystem, WrapObjectsSystem,
+};
+use ncollide2d::world::CollisionWorld;
+use rand::Rng;
+use roid_rage_grpc::roid_rage as rpc;
+use specs::prelude::*;
+use std::path::Path;
+use std::time::Duration;
+
+/// The game simulation: the specs `World` and the `Dispatcher` that advances it.
+///
+/// This knows nothing about rendering, so it can be driven by the ggez event loop or
+/// stepped directly, e.g. by the headless runner.
+///
+/// All randomness comes from the seed, so given the same seed and the same pilot commands
+/// every step produces exactly the same world.
+pub struct Simulation {
+    world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+    seed: u64,
+}
+
+impl Simulation {
+    pub fn new(settings: Settings) -> std::io::Result<Simulation> {
+        let seed = if settings.seed == 0 {
+            rand::rng().random()
+        } else {
+            settings.seed
+        };
+
+        let mut world = World::new();
+
+        world.insert(Field::new(settings.screen_width, settings.screen_height));
+        world.insert(CollisionWorld::<f32, specs::world::Index>::new(0.02f32));
+        world.insert(TimeDelta(Duration::from_secs(0)));
+        world.insert(GameRng::from_seed(seed));
+        world.insert(DepartedPilots::default());
+        world.insert(GameProgress::default());
+        world.insert(settings);
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        world.insert(runtime);
+
+        let query_pilot_system = QueryPilotSystem::new()?;
+
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(PilotRegistrationSystem::new(), "pilot-registration", &[])
+            .with(
+                CleanupCollisionsSystem::default(),
+                "collision-system-maintenance",
+                &[],
+            )
+            .with(
+                RepopulateSystem,
+                "repopulate",
+                &["collision-system-maintenance"],
+            )
+            .with(
+                RespawnShipSystem,
+                "respawn",
+                &["pilot-registration", "collision-system-maintenance"],
+            )
+            .with(AgeFragmentsSystem, "age_fragments", &[])
+            .with(SteerMissilesSystem, "steer_missiles", &[])
+            .with(
+                ArmMinesSystem,
+                "arm_mines",
+                &["collision-system-maintenance"],
+            )
+            .with(
+                MoveObjectsSystem,
+                "move_objects",
+                &["repopulate", "respawn", "steer_missiles"],
+            )
+            .with(
+                DetectCollisionsSystem::default(),
+                "detect_collisions",
+                &["move_objects", "arm_mines"],
+            )
+            .with(WrapObjectsSystem, "wrap_objects", &["detect_collisions"])
+            .with(
+                RemoveOutOfBoundsSystem,
+                "remove_out_of_bounds",
+                &["detect_collisions"],
+            )
+            .with(
+                ExplodeRoidsSystem,
+                "explode_roids",
+                &["remove_out_of_bounds"],
+            )
+            .with(
+                ExplodeBulletsSystem,
+                "explode_bullets",
+                &["remove_out_of_bounds"],
+            )
+            .with(
+                CollectPowerUpsSystem,
+                "collect_power_ups",
+                &["remove_out_of_bounds"],
+            )
+            .with(AgePowerUpsSystem, "age_power_ups", &["collect_power_ups"])
+            // Ships collect power-ups before explosions are decided, so a shield saves them.
+            .with(
+                ExplodeShipsSystem,
+                "explode_ships",
+                &["remove_out_of_bounds", "collect_power_ups"],
+            )
+            .with(
+                RechargeShieldsSystem,
+                "recharge_shields",
+                &["explode_ships"],
+            )
+            // Entities are created in the order systems run, so the pilots are queried after
+            // the explosions to keep entity ids (and hence the game) deterministic.
+            .with(
+                query_pilot_system,
+                "query_pilot",
+                &[
+                    "wrap_objects",
+                    "remove_out_of_bounds",
+                    "explode_roids",
+                    "explode_bullets",
+                    "explode_ships",
+                    "age_power_ups",
+                    "recharge_shields",
+                ],
+            )
+            .with(FireWeaponsSystem, "fire_weapons", &["query_pilot"])
+            .with(EndGameSystem, "end_game", &["query_pilot"])
+            // .with(LoggingSystem, "logging", &["out_of_bounds"])
+            .build();
+
+        dispatcher.setup(&mut world);
+
+        Ok(Simulation {
+            world,
+            dispatcher,
+            seed,
+        })
+    }
+
+    /// The seed the simulation's random numbers are drawn from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of ticks per second of game time.
+    pub fn tick_rate(&self) -> u32 {
+        self.world.read_resource::<Settings>().tick_rate
+    }
+
+    /// The fixed length of a single tick.
+    pub fn time_step(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate() as f64)
+    }
+
+    /// Record every following tick to a replay file at `path`.
+    pub fn record_to(&mut self, path: &Path) -> std::io::Result<()> {
+        let header = {
+            let field = self.world.read_resource::<Field<f32>>();
+            rpc::ReplayHeader {
+                seed: self.seed,
+                tick_rate: self.tick_rate(),
+                field: Some(rpc::Field {
+                    width: field.width() as i32,
+                    height: field.height() as i32,
+                }),
+            }
+        };
+        let recorder = ReplayRecorder::create(path, &header)?;
+        self.world.insert(Some(recorder));
+        Ok(())
+    }
+
+    /// Advance the simulation by one tick of length `time_delta`.
+    pub fn step(&mut self, time_delta: Duration) {
+        {
+            let mut delta = self.world.write_resource::<TimeDelta>();
+            *delta = TimeDelta(time_delta);
+        }
+        self.dispatcher.dispatch(&self.world);
+        self.world.maintain();
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The current statistics for every registered pilot.
+    pub fn pilot_stats(&self) -> Vec<rpc::PilotStats> {
+        (
+            &self.world.read_storage::<Pilot>(),
+            &self.world.read_storage::<Stats>(),
+        )
+            .join()
+            .map(|(pilot, stats)| stats.to_rpc(pilot))
+            .collect()
+    }
+
+    /// The final statistics of every pilot which has left the game, in the order they left.
+    pub fn departed_pilot_stats(&self) -> Vec<rpc::PilotStats> {
+        self.world.read_resource::<DepartedPilots>().0.clone()
+    }
+
+    /// Whether the game mode's end condition has been reached. The simulation can still be
+    /// stepped afterwards, but the results won't mean much.
+    pub fn is_over(&self) -> bool {
+        self.world.read_resource::<GameProgress>().outcome.is_some()
+    }
+
+    /// A summary of the game so far, including pilots which have left.
+    pub fn results(&self) -> GameResults {
+        let progress = self.world.read_resource::<GameProgress>();
+        let mut pilots = self.departed_pilot_stats();
+        pilots.extend(self.pilot_stats());
+        GameResults::new(
+            GameMode::from_settings(&self.world.read_resource::<Settings>()),
+            progress.outcome,
+            progress.wave,
+            progress.elapsed,
+            pilots,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Position, Roid};
+
+    fn settings() -> Settings {
+        let mut settings = Settings::load().expect("settings");
+        settings.pilot_registration_url = "127.0.0.1:0".parse().unwrap();
+        settings
+    }
+
+    #[test]
+    fn step_populates_field() {
+        let settings = settings();
+        let roid_count = settings.initial_roid_count as usize;
+        let mut simulation = Simulation::new(settings).expect("simulation");
+
+        simulation.step(simulation.time_step());
+
+        let roids = simulation.world().read_storage::<Roid>();
+        assert_eq!((&roids).join().count(), roid_count);
+        assert_eq!(simulation.results().wave, 1);
+    }
+
+    #[test]
+    fn game_does_not_end_without_pilots() {
+        let mut settings = settings();
+        settings.game_mode = "time_attack".to_string();
+        settings.time_attack_seconds = 0.0;
+        let mut simulation = Simulation::new(settings).expect("simulation");
+
+        simulation.step(simulation.time_step());
+
+        assert!(!simulation.is_over());
+        assert_eq!(simulation.results().elapsed, 0.0);
+    }
+
+    fn roid_positions(simulation: &Simulation) -> Vec<(f32, f32)> {
+        let roids = simulation.world().read_storage::<Roid>();
+        let positions = simulation.world().read_storage::<Position>();
+        (&roids, &positions)
+            .join()
+            .map(|(_, position)| (position.0.x, position.0.y))
+            .collect()
+    }
+
+    fn run_seeded(seed: u64, ticks: u32) -> Vec<(f32, f32)> {
+        let mut settings = settings();
+        settings.seed = seed;
+        let mut simulation = Simulation::new(settings).expect("simulation");
+        for _ in 0..ticks {
+            simulation.step(simulation.time_step());
+        }
+        roid_positions(&simulation)
+    }
+
+    #[test]
+    fn same_seed_gives_identical_games() {
+        assert_eq!(run_seeded(42, 600), run_seeded(42, 600));
+    }
+
+    #[test]
+    fn different_seeds_give_different_games() {
+        assert_ne!(run_seeded(1, 1), run_seeded(2, 1));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::components::{Invulnerable, PowerUp, PowerUpEffects, TimeDelta};
+use specs::{Entities, Join, Read, System, WriteStorage};
+
+pub struct AgePowerUpsSystem;
+
+/// Remove power-ups nobody collected in time, and run down the timers on collected ones.
+impl<'s> System<'s> for AgePowerUpsSystem {
+    type SystemData = (
+        WriteStorage<'s, PowerUp>,
+        WriteStorage<'s, PowerUpEffects>,
+        WriteStorage<'s, Invulnerable>,
+        Entities<'s>,
+        Read<'s, TimeDelta>,
+    );
+
+    fn run(
+        &mut self,
+        (mut power_ups, mut effects, mut invulnerable, entities, time_delta): Self::SystemData,
+    ) {
+        let dt = time_delta.0.as_secs_f32();
+
+        for (power_up, entity) in (&mut power_ups, &entities).join() {
+            power_up.time_left -= dt;
+            if power_up.time_left <= 0.0 {
+                if let Err(e) = entities.delete(entity) {
+                    println!("Error deleting power-up: {}", e);
+                }
+            }
+        }
+
+        for effects in (&mut effects).join() {
+            effects.rapid_fire = (effects.rapid_fire - dt).max(0.0);
+            effects.spread_shot = (effects.spread_shot - dt).max(0.0);
+        }
+
+        let mut expired = Vec::new();
+        for (invulnerable, entity) in (&mut invulnerable, &entities).join() {
+            invulnerable.0 -= dt;
+            if invulnerable.0 <= 0.0 {
+                expired.push(entity);
+            }
+        }
+        for entity in expired {
+            invulnerable.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::PowerUpKind;
+    use specs::{Builder, RunNow, World, WorldExt};
+    use std::time::Duration;
+
+    #[test]
+    fn power_ups_and_effects_expire() {
+        let mut world = World::new();
+        world.register::<PowerUp>();
+        world.register::<PowerUpEffects>();
+        world.register::<Invulnerable>();
+        world.insert(TimeDelta(Duration::from_secs_f32(1.0)));
+
+        let power_up = world
+            .create_entity()
+            .with(PowerUp {
+                kind: PowerUpKind::Shield,
+                time_left: 1.5,
+            })
+            .build();
+        let ship = world
+            .create_entity()
+            .with(PowerUpEffects {
+                rapid_fire: 1.5,
+                spread_shot: 0.5,
+            })
+            .with(Invulnerable(1.5))
+            .build();
+
+        AgePowerUpsSystem.run_now(&world);
+        world.maintain();
+        assert!(world.entities().is_alive(power_up));
+        assert!(world.read_storage::<Invulnerable>().contains(ship));
+        assert_eq!(
+            world.read_storage::<PowerUpEffects>().get(ship).copied(),
+            Some(PowerUpEffects {
+                rapid_fire: 0.5,
+                spread_shot: 0.0,
+            })
+        );
+
+        AgePowerUpsSystem.run_now(&world);
+        world.maintain();
+        assert!(!world.entities().is_alive(power_up));
+        assert!(!world.read_storage::<Invulnerable>().contains(ship));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::components::{weapon_collision_groups, CollisionHandle, Mine, TimeDelta};
+use crate::settings::Settings;
+use ncollide2d::world::CollisionWorld;
+use specs::{Entities, Join, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+
+pub struct ArmMinesSystem;
+
+/// Arm mines once their delay is up, and remove those which have been around too long.
+impl<'s> System<'s> for ArmMinesSystem {
+    type SystemData = (
+        WriteStorage<'s, Mine>,
+        ReadStorage<'s, CollisionHandle>,
+        Entities<'s>,
+        Read<'s, TimeDelta>,
+        ReadExpect<'s, Settings>,
+        WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
+    );
+
+    fn run(
+        &mut self,
+        (mut mines, handles, entities, time_delta, settings, mut collision_world): Self::SystemData,
+    ) {
+        let dt = time_delta.0.as_secs_f32();
+
+        for (mine, handle, entity) in (&mut mines, &handles, &entities).join() {
+            mine.time_left -= dt;
+            if mine.time_left <= 0.0 {
+                if let Err(e) = entities.delete(entity) {
+                    println!("Error deleting mine: {}", e);
+                }
+                continue;
+            }
+
+            if !mine.is_armed() {
+                mine.time_to_arm -= dt;
+                if mine.is_armed() {
+                    if let Some(collision_object) = collision_world.get_mut(handle.0) {
+                        collision_object
+                            .set_collision_groups(weapon_collision_groups(settings.pvp));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{make_mine, Bullet, LinearVelocity, Position};
+    use specs::{Builder, RunNow, World, WorldExt};
+    use std::time::Duration;
+
+    #[test]
+    fn mines_arm_then_expire() {
+        let mut world = World::new();
+        world.register::<Mine>();
+        world.register::<Bullet>();
+        world.register::<LinearVelocity>();
+        world.register::<Position>();
+        world.register::<CollisionHandle>();
+        world.insert(Settings::load().expect("settings"));
+        world.insert(TimeDelta(Duration::from_secs_f32(1.0)));
+        world.insert(CollisionWorld::<f32, specs::world::Index>::new(0.02f32));
+
+        let owner = world.create_entity().build();
+        let mine = {
+            let mut collision_world =
+                world.write_resource::<CollisionWorld<f32, specs::world::Index>>();
+            let builder = world.create_entity_unchecked();
+            let entity = builder.entity;
+            make_mine(
+                builder,
+                owner,
+                glam::Vec2::ZERO,
+                0.5,
+                1.5,
+                &mut collision_world,
+            );
+            entity
+        };
+        // The groups the mine can hit
+        let targets = |world: &World| {
+            let handle = world.read_storage::<CollisionHandle>().get(mine).unwrap().0;
+            let collision_world = world.read_resource::<CollisionWorld<f32, specs::world::Index>>();
+            let groups = collision_world
+                .collision_object(handle)
+                .unwrap()
+                .collision_groups();
+            (0..30)
+                .filter(|group| groups.can_interact_with(*group))
+                .collect::<Vec<_>>()
+        };
+        assert!(targets(&world).is_empty());
+
+        ArmMinesSystem.run_now(&world);
+        world.maintain();
+        assert!(world.read_storage::<Mine>().get(mine).unwrap().is_armed());
+        assert!(!targets(&world).is_empty());
+
+        ArmMinesSystem.run_now(&world);
+        world.maintain();
+        assert!(!world.entities().is_alive(mine));
+    }
+}
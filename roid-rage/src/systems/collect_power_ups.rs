@@ -0,0 +1,194 @@
+use crate::components::{
+    Collision, Invulnerable, Pilot, PowerUp, PowerUpEffects, PowerUpKind, Ship, ShipResources,
+};
+use crate::game_mode::GameMode;
+use crate::settings::Settings;
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteStorage};
+
+pub struct CollectPowerUpsSystem;
+
+/// Give power-ups to the ships which fly into them.
+impl<'s> System<'s> for CollectPowerUpsSystem {
+    type SystemData = (
+        ReadStorage<'s, Collision>,
+        ReadStorage<'s, PowerUp>,
+        ReadStorage<'s, Ship>,
+        WriteStorage<'s, Pilot>,
+        WriteStorage<'s, ShipResources>,
+        WriteStorage<'s, PowerUpEffects>,
+        WriteStorage<'s, Invulnerable>,
+        Entities<'s>,
+        ReadExpect<'s, Settings>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            collisions,
+            power_ups,
+            ships,
+            mut pilots,
+            mut resources,
+            mut effects,
+            mut invulnerable,
+            entities,
+            settings,
+        ): Self::SystemData,
+    ) {
+        let duration = settings.power_up_duration;
+        let mode = GameMode::from_settings(&settings);
+        for (collision, power_up, entity) in (&collisions, &power_ups, &entities).join() {
+            // If several ships reach it at once, the first one gets it.
+            let ship = match collision.with.iter().find(|e| ships.contains(**e)) {
+                Some(ship) => *ship,
+                None => continue,
+            };
+
+            match power_up.kind {
+                PowerUpKind::RapidFire => {
+                    if let Ok(entry) = effects.entry(ship) {
+                        entry.or_insert_with(PowerUpEffects::default).rapid_fire = duration;
+                    }
+                }
+                PowerUpKind::SpreadShot => {
+                    if let Ok(entry) = effects.entry(ship) {
+                        entry.or_insert_with(PowerUpEffects::default).spread_shot = duration;
+                    }
+                }
+                PowerUpKind::Shield => {
+                    let time_left = invulnerable.get(ship).map_or(0.0, |i| i.0);
+                    if let Err(e) = invulnerable.insert(ship, Invulnerable(time_left.max(duration)))
+                    {
+                        println!("Error shielding ship: {}", e);
+                    }
+                }
+                // Survival pilots only ever get one life.
+                PowerUpKind::ExtraLife if mode == GameMode::Survival => {}
+                PowerUpKind::ExtraLife => {
+                    if let Some(pilot) = pilots.get_mut(ship) {
+                        pilot.lives += 1;
+                    }
+                }
+                PowerUpKind::FuelRefill => {
+                    if let Some(resources) = resources.get_mut(ship) {
+                        resources.fuel = resources.fuel_capacity;
+                    }
+                }
+            }
+
+            if let Err(e) = entities.delete(entity) {
+                println!("Error deleting power-up: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Cannon, Weapon};
+    use specs::{Builder, Entity, RunNow, World, WorldExt};
+
+    fn world(game_mode: &str) -> World {
+        let mut settings = Settings::load().expect("settings");
+        settings.game_mode = game_mode.to_string();
+
+        let mut world = World::new();
+        world.register::<Collision>();
+        world.register::<PowerUp>();
+        world.register::<Ship>();
+        world.register::<Pilot>();
+        world.register::<ShipResources>();
+        world.register::<PowerUpEffects>();
+        world.register::<Invulnerable>();
+        world.insert(settings);
+        world
+    }
+
+    fn collect(kind: PowerUpKind) -> (World, Entity, Entity) {
+        collect_in_mode(kind, "endless")
+    }
+
+    fn collect_in_mode(kind: PowerUpKind, game_mode: &str) -> (World, Entity, Entity) {
+        let mut world = world(game_mode);
+        let ship = world
+            .create_entity()
+            .with(Pilot::new("pilot", 1))
+            .with(Ship::new(
+                10.0,
+                5.0,
+                1.0,
+                1.0,
+                1.0,
+                Cannon {
+                    bullet_speed: 1.0,
+                    rate_of_fire: 1.0,
+                    weapon: Weapon::Gun,
+                },
+            ))
+            .with(ShipResources {
+                fuel: 1.0,
+                fuel_capacity: 10.0,
+                heat: 0.0,
+                heat_capacity: 0.0,
+                energy: 0.0,
+                energy_capacity: 0.0,
+            })
+            .build();
+        let power_up = world
+            .create_entity()
+            .with(PowerUp {
+                kind,
+                time_left: 1.0,
+            })
+            .with(Collision { with: vec![ship] })
+            .build();
+
+        CollectPowerUpsSystem.run_now(&world);
+        world.maintain();
+        (world, ship, power_up)
+    }
+
+    #[test]
+    fn collected_power_ups_disappear() {
+        let (world, _, power_up) = collect(PowerUpKind::RapidFire);
+        assert!(!world.entities().is_alive(power_up));
+    }
+
+    #[test]
+    fn timed_power_ups_last_for_the_duration() {
+        let (world, ship, _) = collect(PowerUpKind::SpreadShot);
+        let duration = world.read_resource::<Settings>().power_up_duration;
+        let effects = world.read_storage::<PowerUpEffects>().get(ship).copied();
+        assert_eq!(effects.map(|e| e.spread_shot), Some(duration));
+
+        let (world, ship, _) = collect(PowerUpKind::Shield);
+        assert!(world.read_storage::<Invulnerable>().contains(ship));
+    }
+
+    #[test]
+    fn extra_life_and_fuel_refill() {
+        let (world, ship, _) = collect(PowerUpKind::ExtraLife);
+        assert_eq!(
+            world.read_storage::<Pilot>().get(ship).map(|p| p.lives),
+            Some(2)
+        );
+
+        let (world, ship, _) = collect(PowerUpKind::FuelRefill);
+        let fuel = world
+            .read_storage::<ShipResources>()
+            .get(ship)
+            .map(|r| r.fuel);
+        assert_eq!(fuel, Some(10.0));
+    }
+
+    #[test]
+    fn no_extra_lives_in_survival() {
+        let (world, ship, power_up) = collect_in_mode(PowerUpKind::ExtraLife, "survival");
+        assert_eq!(
+            world.read_storage::<Pilot>().get(ship).map(|p| p.lives),
+            Some(1)
+        );
+        assert!(!world.entities().is_alive(power_up));
+    }
+}
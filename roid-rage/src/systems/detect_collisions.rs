@@ -1,56 +1,375 @@
 use crate::components::CollisionHandle;
-use crate::components::{Collision, Position};
-use ncollide2d::na::{zero, Isometry2, Vector2};
-use ncollide2d::pipeline::{CollisionObjectSlabHandle, ContactEvent};
+use crate::components::{Bullet, Collision, Pilot, Position, Rotation, Wrapping};
+use crate::core::field::Field;
+use crate::settings::Settings;
+use ncollide2d::na::{Isometry2, Vector2};
+use ncollide2d::pipeline::CollisionObjectSlabHandle;
+use ncollide2d::query::{proximity, Proximity};
 use ncollide2d::world::CollisionWorld;
-use specs::{Entities, Join, ReadStorage, System, WriteExpect, WriteStorage};
-use std::collections::HashSet;
+use specs::{
+    Entities, Entity, Join, LendJoin, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
+};
+use std::collections::{BTreeSet, HashMap};
 
-pub struct DetectCollisionsSystem;
+type HandlePair = (CollisionObjectSlabHandle, CollisionObjectSlabHandle);
+type EntityPair = (Entity, Entity);
+
+/// Find collisions between objects, including across the edges of the field.
+///
+/// The collision world only knows where objects actually are, so something hanging off the
+/// right edge can't hit something at the left edge by itself. We handle this by checking the
+/// images of wrapping objects in the neighbouring copies of the field. We remember which pairs
+/// touch, whether directly or that way, so that only new contacts count; a pair which moves across
+/// an edge while touching isn't hit again.
+///
+/// In PvP games bullets can hit ships, but never the ship of the pilot who fired them, nor a
+/// team-mate's ship unless friendly fire is on.
+#[derive(Default)]
+pub struct DetectCollisionsSystem {
+    contacts: BTreeSet<EntityPair>,
+}
 
 impl<'s> System<'s> for DetectCollisionsSystem {
     type SystemData = (
         ReadStorage<'s, Position>,
+        ReadStorage<'s, Rotation>,
         ReadStorage<'s, CollisionHandle>,
+        ReadStorage<'s, Wrapping>,
+        ReadStorage<'s, Bullet>,
+        ReadStorage<'s, Pilot>,
         WriteStorage<'s, Collision>,
         Entities<'s>,
+        ReadExpect<'s, Field<f32>>,
+        ReadExpect<'s, Settings>,
         WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
     );
 
     fn run(
         &mut self,
-        (positions, collision_handles, mut collision_markers, entities, mut collision_world): Self::SystemData,
+        (
+            positions,
+            rotations,
+            collision_handles,
+            wrapping,
+            bullets,
+            pilots,
+            mut collision_markers,
+            entities,
+            field,
+            settings,
+            mut collision_world,
+        ): Self::SystemData,
     ) {
-        for (position, handle) in (&positions, &collision_handles).join() {
+        for (position, rotation, handle) in
+            (&positions, (&rotations).maybe(), &collision_handles).join()
+        {
+            let angle = rotation.map_or(0.0, |r| r.0);
             if let Some(collision_object) = collision_world.get_mut(handle.0) {
                 collision_object.set_position(Isometry2::new(
                     Vector2::new(position.0.x, position.0.y),
-                    zero(),
+                    angle,
                 ));
             }
         }
 
         collision_world.update();
 
-        // Find all collisions
-        let collisions: HashSet<CollisionObjectSlabHandle> = collision_world
-            .contact_events()
-            .iter()
-            .filter_map(|event| match event {
-                ContactEvent::Started(collider1, collider2) => Some(vec![*collider1, *collider2]),
-                _ => None,
+        // Every pair touching this tick, directly or across an edge. These are kept in order so
+        // that the collisions are recorded in the same order from run to run.
+        let entity_for = |handle| {
+            collision_world
+                .collision_object(handle)
+                .map(|obj| entities.entity(*obj.data()))
+        };
+        let contacts: BTreeSet<EntityPair> = collision_world
+            .contact_pairs(false)
+            .filter(|(_, _, _, manifold)| manifold.len() > 0)
+            .map(|(collider1, collider2, _, _)| (collider1, collider2))
+            .chain(wrapped_contacts(
+                &collision_handles,
+                &positions,
+                &wrapping,
+                &field,
+                &collision_world,
+            ))
+            .filter_map(|(collider1, collider2)| {
+                let (entity1, entity2) = (entity_for(collider1)?, entity_for(collider2)?);
+                Some((entity1.min(entity2), entity1.max(entity2)))
             })
-            .flatten()
             .collect();
 
+        // Find all new collisions, noting for each entity the entities it hit
+        let mut collisions: HashMap<Entity, Vec<Entity>> = HashMap::new();
+        for &(entity1, entity2) in contacts.difference(&self.contacts) {
+            if can_hit(entity1, entity2, &bullets, &pilots, settings.friendly_fire) {
+                collisions.entry(entity1).or_default().push(entity2);
+                collisions.entry(entity2).or_default().push(entity1);
+            }
+        }
+        self.contacts = contacts;
+
         // Record collisions
-        for (handle, entity) in (&collision_handles, &entities).join() {
-            if collisions.contains(&handle.0) {
-                match collision_markers.insert(entity, Collision {}) {
-                    Err(e) => println!("Error creating collision record: {}", e),
-                    _ => {}
+        for (_, entity) in (&collision_handles, &entities).join() {
+            if let Some(with) = collisions.remove(&entity) {
+                // Laser hits are recorded as the laser is fired, so add to any already there
+                if let Some(collision) = collision_markers.get_mut(entity) {
+                    collision.with.extend(with);
+                } else if let Err(e) = collision_markers.insert(entity, Collision { with }) {
+                    println!("Error creating collision record: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Whether a contact between two entities counts as a collision.
+fn can_hit(
+    entity1: Entity,
+    entity2: Entity,
+    bullets: &ReadStorage<Bullet>,
+    pilots: &ReadStorage<Pilot>,
+    friendly_fire: bool,
+) -> bool {
+    let shot = |bullet, target| bullets.get(bullet).map(|b: &Bullet| (b.owner, target));
+    let (shooter, target) = match shot(entity1, entity2).or_else(|| shot(entity2, entity1)) {
+        Some(shot) => shot,
+        None => return true,
+    };
+    if shooter == target {
+        return false;
+    }
+    match (pilots.get(shooter), pilots.get(target)) {
+        (Some(shooter), Some(target)) => friendly_fire || !shooter.is_teammate(target),
+        _ => true,
+    }
+}
+
+/// Pairs of objects which touch across the edges of the field.
+fn wrapped_contacts(
+    collision_handles: &ReadStorage<CollisionHandle>,
+    positions: &ReadStorage<Position>,
+    wrapping: &ReadStorage<Wrapping>,
+    field: &Field<f32>,
+    collision_world: &CollisionWorld<f32, specs::world::Index>,
+) -> BTreeSet<HandlePair> {
+    let mut contacts = BTreeSet::new();
+    for (handle, position, _wrapping) in (collision_handles, positions, wrapping).join() {
+        let object = match collision_world.collision_object(handle.0) {
+            Some(object) => object,
+            None => continue,
+        };
+        let angle = object.position().rotation.angle();
+
+        // The first image is the object itself, which the collision world has already handled
+        for (x, y) in field.images(position.0.x, position.0.y).iter().skip(1) {
+            let image = Isometry2::new(Vector2::new(*x, *y), angle);
+            let aabb = object.shape().aabb(&image);
+            for (other_handle, other) in
+                collision_world.interferences_with_aabb(&aabb, object.collision_groups())
+            {
+                if other_handle == handle.0 {
+                    continue;
+                }
+                let touching = proximity(
+                    &image,
+                    object.shape().as_ref(),
+                    other.position(),
+                    other.shape().as_ref(),
+                    0.0,
+                ) == Proximity::Intersecting;
+                if touching {
+                    contacts.insert((handle.0.min(other_handle), handle.0.max(other_handle)));
                 }
             }
         }
     }
+    contacts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Weapon;
+    use ncollide2d::pipeline::{CollisionGroups, GeometricQueryType};
+    use ncollide2d::shape::{Ball, ShapeHandle};
+    use specs::{Builder, RunNow, World, WorldExt};
+
+    fn make_world() -> World {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Rotation>();
+        world.register::<CollisionHandle>();
+        world.register::<Wrapping>();
+        world.register::<Collision>();
+        world.register::<Bullet>();
+        world.register::<Pilot>();
+        world.insert(Field::new(100.0_f32, 100.0_f32));
+        world.insert(Settings::load().expect("settings"));
+        world.insert(CollisionWorld::<f32, specs::world::Index>::new(0.02));
+        world
+    }
+
+    fn add_ball(world: &mut World, x: f32, y: f32) -> Entity {
+        let entity = world
+            .create_entity()
+            .with(Position(glam::Vec2::new(x, y)))
+            .with(Wrapping)
+            .build();
+        let handle = {
+            let mut collision_world =
+                world.write_resource::<CollisionWorld<f32, specs::world::Index>>();
+            collision_world
+                .add(
+                    Isometry2::new(Vector2::new(x, y), 0.0),
+                    ShapeHandle::new(Ball::new(5.0)),
+                    CollisionGroups::new(),
+                    GeometricQueryType::Contacts(0.0, 0.0),
+                    entity.id(),
+                )
+                .0
+        };
+        world
+            .write_storage::<CollisionHandle>()
+            .insert(entity, CollisionHandle(handle))
+            .unwrap();
+        entity
+    }
+
+    fn collided_with(world: &World, entity: Entity) -> Vec<Entity> {
+        world
+            .read_storage::<Collision>()
+            .get(entity)
+            .map_or_else(Vec::new, |c| c.with.clone())
+    }
+
+    #[test]
+    fn collides_across_edges() {
+        let mut world = make_world();
+        let left = add_ball(&mut world, 2.0, 50.0);
+        let right = add_ball(&mut world, 97.0, 50.0);
+
+        DetectCollisionsSystem::default().run_now(&world);
+
+        assert_eq!(collided_with(&world, left), vec![right]);
+        assert_eq!(collided_with(&world, right), vec![left]);
+    }
+
+    #[test]
+    fn collides_across_corners() {
+        let mut world = make_world();
+        let top_left = add_ball(&mut world, 1.0, 1.0);
+        let bottom_right = add_ball(&mut world, 99.0, 99.0);
+
+        DetectCollisionsSystem::default().run_now(&world);
+
+        assert_eq!(collided_with(&world, top_left), vec![bottom_right]);
+    }
+
+    #[test]
+    fn distant_objects_do_not_collide() {
+        let mut world = make_world();
+        let left = add_ball(&mut world, 2.0, 50.0);
+        add_ball(&mut world, 50.0, 50.0);
+
+        DetectCollisionsSystem::default().run_now(&world);
+
+        assert!(collided_with(&world, left).is_empty());
+    }
+
+    #[test]
+    fn wrapped_contacts_are_only_reported_when_they_start() {
+        let mut world = make_world();
+        let left = add_ball(&mut world, 2.0, 50.0);
+        add_ball(&mut world, 97.0, 50.0);
+
+        let mut system = DetectCollisionsSystem::default();
+        system.run_now(&world);
+        world.write_storage::<Collision>().clear();
+        system.run_now(&world);
+
+        assert!(collided_with(&world, left).is_empty());
+    }
+
+    #[test]
+    fn contacts_moving_across_an_edge_are_not_reported_again() {
+        let mut world = make_world();
+        let left = add_ball(&mut world, 93.0, 50.0);
+        let right = add_ball(&mut world, 99.0, 50.0);
+        let mut system = DetectCollisionsSystem::default();
+
+        system.run_now(&world);
+        assert_eq!(collided_with(&world, left), vec![right]);
+
+        // Wrap the right ball around to the other edge, and then back again
+        for x in [1.0, 99.0] {
+            world.write_storage::<Collision>().clear();
+            world
+                .write_storage::<Position>()
+                .insert(right, Position(glam::Vec2::new(x, 50.0)))
+                .unwrap();
+            system.run_now(&world);
+
+            assert!(collided_with(&world, left).is_empty());
+            assert!(collided_with(&world, right).is_empty());
+        }
+    }
+
+    fn add_pilot(world: &mut World, x: f32, y: f32, team: &str) -> Entity {
+        let entity = add_ball(world, x, y);
+        let mut pilot = Pilot::new("pilot", 3);
+        pilot.team = team.to_string();
+        world
+            .write_storage::<Pilot>()
+            .insert(entity, pilot)
+            .unwrap();
+        entity
+    }
+
+    fn add_bullet(world: &mut World, x: f32, y: f32, owner: Entity) -> Entity {
+        let entity = add_ball(world, x, y);
+        world
+            .write_storage::<Bullet>()
+            .insert(entity, Bullet::new(owner, Weapon::Gun))
+            .unwrap();
+        entity
+    }
+
+    #[test]
+    fn bullets_do_not_hit_their_owner() {
+        let mut world = make_world();
+        let pilot = add_pilot(&mut world, 50.0, 50.0, "");
+        let bullet = add_bullet(&mut world, 52.0, 50.0, pilot);
+
+        DetectCollisionsSystem::default().run_now(&world);
+
+        assert!(collided_with(&world, pilot).is_empty());
+        assert!(collided_with(&world, bullet).is_empty());
+    }
+
+    #[test]
+    fn bullets_hit_other_pilots() {
+        let mut world = make_world();
+        let shooter = add_pilot(&mut world, 10.0, 50.0, "");
+        let target = add_pilot(&mut world, 50.0, 50.0, "");
+        let bullet = add_bullet(&mut world, 52.0, 50.0, shooter);
+
+        DetectCollisionsSystem::default().run_now(&world);
+
+        assert_eq!(collided_with(&world, target), vec![bullet]);
+    }
+
+    #[test]
+    fn friendly_fire_is_configurable() {
+        for friendly_fire in [false, true] {
+            let mut world = make_world();
+            world.write_resource::<Settings>().friendly_fire = friendly_fire;
+            let shooter = add_pilot(&mut world, 10.0, 50.0, "red");
+            let target = add_pilot(&mut world, 50.0, 50.0, "red");
+            add_bullet(&mut world, 52.0, 50.0, shooter);
+
+            DetectCollisionsSystem::default().run_now(&world);
+
+            assert_eq!(!collided_with(&world, target).is_empty(), friendly_fire);
+        }
+    }
 }
@@ -0,0 +1,96 @@
+use crate::components::{DepartedPilots, GameProgress, Pilot, TimeDelta};
+use crate::game_mode::GameMode;
+use crate::settings::Settings;
+use specs::{Join, Read, ReadExpect, ReadStorage, System, Write};
+
+/// Keep the game clock and decide when the game is over.
+///
+/// The clock only starts once a pilot has joined, so that time spent waiting for pilots doesn't
+/// count against them.
+pub struct EndGameSystem;
+
+impl<'s> System<'s> for EndGameSystem {
+    type SystemData = (
+        ReadStorage<'s, Pilot>,
+        ReadExpect<'s, DepartedPilots>,
+        Read<'s, TimeDelta>,
+        ReadExpect<'s, Settings>,
+        Write<'s, GameProgress>,
+    );
+
+    fn run(&mut self, (pilots, departed, time_delta, settings, mut progress): Self::SystemData) {
+        if progress.outcome.is_some() {
+            return;
+        }
+        if pilots.is_empty() && departed.0.is_empty() {
+            return;
+        }
+
+        progress.elapsed += time_delta.0.as_secs_f32();
+
+        let pilots_out = (&pilots).join().all(|pilot| pilot.lives == 0);
+        progress.outcome =
+            GameMode::from_settings(&settings).outcome(pilots_out, progress.elapsed, &settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_mode::Outcome;
+    use specs::{Builder, RunNow, World, WorldExt};
+    use std::time::Duration;
+
+    fn world(game_mode: &str) -> World {
+        let mut world = World::new();
+        world.register::<Pilot>();
+        let mut settings = Settings::load().expect("settings");
+        settings.game_mode = game_mode.to_string();
+        settings.time_attack_seconds = 1.0;
+        world.insert(settings);
+        world.insert(DepartedPilots::default());
+        world.insert(TimeDelta(Duration::from_secs_f32(0.75)));
+        world.insert(GameProgress::default());
+        world
+    }
+
+    fn outcome(world: &World) -> Option<Outcome> {
+        world.read_resource::<GameProgress>().outcome
+    }
+
+    #[test]
+    fn clock_waits_for_pilots() {
+        let world = world("time_attack");
+
+        EndGameSystem.run_now(&world);
+        EndGameSystem.run_now(&world);
+
+        assert_eq!(world.read_resource::<GameProgress>().elapsed, 0.0);
+        assert_eq!(outcome(&world), None);
+    }
+
+    #[test]
+    fn time_attack_ends_when_time_is_up() {
+        let mut world = world("time_attack");
+        world.create_entity().with(Pilot::new("pilot", 3)).build();
+
+        EndGameSystem.run_now(&world);
+        assert_eq!(outcome(&world), None);
+
+        EndGameSystem.run_now(&world);
+        assert_eq!(outcome(&world), Some(Outcome::TimeUp));
+    }
+
+    #[test]
+    fn survival_ends_when_pilots_are_out() {
+        let mut world = world("survival");
+        let pilot = world.create_entity().with(Pilot::new("pilot", 1)).build();
+
+        EndGameSystem.run_now(&world);
+        assert_eq!(outcome(&world), None);
+
+        world.write_storage::<Pilot>().get_mut(pilot).unwrap().lives = 0;
+        EndGameSystem.run_now(&world);
+        assert_eq!(outcome(&world), Some(Outcome::PilotsOut));
+    }
+}
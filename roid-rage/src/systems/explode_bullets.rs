@@ -1,7 +1,7 @@
-use crate::components::{make_fragment, Bullet, Collision, Position};
+use crate::components::{make_fragment, Bullet, Collision, GameRng, Position, Stats};
 use crate::core::util::random_bearing;
 use rand::Rng;
-use specs::{Entities, Join, LazyUpdate, Read, ReadStorage, System};
+use specs::{Entities, Join, LazyUpdate, Read, ReadStorage, System, WriteExpect, WriteStorage};
 
 pub struct ExplodeBulletsSystem;
 
@@ -11,16 +11,25 @@ impl<'s> System<'s> for ExplodeBulletsSystem {
         ReadStorage<'s, Collision>,
         ReadStorage<'s, Bullet>,
         ReadStorage<'s, Position>,
+        WriteStorage<'s, Stats>,
         Entities<'s>,
+        WriteExpect<'s, GameRng>,
         Read<'s, LazyUpdate>,
     );
 
-    fn run(&mut self, (collisions, bullets, positions, entities, lazy): Self::SystemData) {
-        let mut rng = rand::rng();
+    fn run(
+        &mut self,
+        (collisions, bullets, positions, mut stats, entities, mut rng, lazy): Self::SystemData,
+    ) {
+        let rng = &mut rng.0;
 
-        for (_collision, _bullet, position, entity) in
+        for (_collision, bullet, position, entity) in
             (&collisions, &bullets, &positions, &entities).join()
         {
+            if let Some(stats) = stats.get_mut(bullet.owner) {
+                stats.hits += 1;
+            }
+
             match entities.delete(entity) {
                 Err(e) => println!("Error deleting bullet: {}", e),
                 _ => {}
@@ -36,7 +45,8 @@ impl<'s> System<'s> for ExplodeBulletsSystem {
                     },
                     position.0.x,
                     position.0.y,
-                    random_bearing(),
+                    random_bearing(rng),
+                    rng,
                 );
             }
         }
@@ -1,13 +1,18 @@
-use crate::components::{make_roid, AngularVelocity, Collision, LinearVelocity, Position, Roid};
+use crate::components::{
+    make_power_up, make_roid, AngularVelocity, Bullet, Collision, GameRng, LinearVelocity,
+    Position, PowerUpKind, Roid, Stats,
+};
 use crate::core::util::random_bearing;
-use sted::Velocity;
 use crate::settings::Settings;
 use ncollide2d::world::CollisionWorld;
-use specs::{Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect};
+use specs::{
+    Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
+};
+use sted::Velocity;
 
 pub struct ExplodeRoidsSystem;
 
-/// Explode roids that have collided with something.
+/// Explode roids that have collided with something, sometimes leaving a power-up behind.
 impl<'s> System<'s> for ExplodeRoidsSystem {
     type SystemData = (
         ReadStorage<'s, Collision>,
@@ -15,9 +20,12 @@ impl<'s> System<'s> for ExplodeRoidsSystem {
         ReadStorage<'s, LinearVelocity>,
         ReadStorage<'s, AngularVelocity>,
         ReadStorage<'s, Position>,
+        ReadStorage<'s, Bullet>,
+        WriteStorage<'s, Stats>,
         Entities<'s>,
         WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
         ReadExpect<'s, Settings>,
+        WriteExpect<'s, GameRng>,
         Read<'s, LazyUpdate>,
     );
 
@@ -29,13 +37,18 @@ impl<'s> System<'s> for ExplodeRoidsSystem {
             linear_motions,
             angular_velocities,
             positions,
+            bullets,
+            mut stats,
             entities,
             mut collision_world,
             settings,
+            mut rng,
             lazy,
         ): Self::SystemData,
     ) {
-        for (_, roid, lm, av, position, entity) in (
+        let rng = &mut rng.0;
+
+        for (collision, roid, lm, av, position, entity) in (
             &collisions,
             &roids,
             &linear_motions,
@@ -50,6 +63,29 @@ impl<'s> System<'s> for ExplodeRoidsSystem {
                 _ => {}
             }
 
+            // Credit the pilot whose bullet destroyed the roid
+            let owner = collision
+                .with
+                .iter()
+                .find_map(|other| bullets.get(*other).map(|bullet| bullet.owner));
+            if let Some(stats) = owner.and_then(|owner| stats.get_mut(owner)) {
+                stats.score += roid.points(&settings);
+            }
+
+            if let Some(kind) = PowerUpKind::random_drop(&settings, rng) {
+                let new_entity = entities.create();
+                make_power_up(
+                    specs::world::LazyBuilder {
+                        entity: new_entity,
+                        lazy: &lazy,
+                    },
+                    kind,
+                    position.0,
+                    settings.power_up_lifetime,
+                    &mut collision_world,
+                );
+            }
+
             if roid.radius >= settings.minimum_roid_radius {
                 for _ in 0..2 {
                     let new_entity = entities.create();
@@ -61,10 +97,11 @@ impl<'s> System<'s> for ExplodeRoidsSystem {
                         position.0.x,
                         position.0.y,
                         lm.0.speed() * 1.5,
-                        random_bearing(),
+                        random_bearing(rng),
                         av.0 * 2.0,
                         roid.radius / 2.0,
                         settings.roid_bumpiness,
+                        rng,
                         &mut collision_world,
                     );
                 }
@@ -0,0 +1,375 @@
+use crate::components::{
+    make_fragment, AngularVelocity, Bullet, Collision, CollisionHandle, GameRng, Health,
+    Invulnerable, LinearVelocity, Pilot, Position, PowerUp, PowerUpEffects, RespawnTimer, Roid,
+    Rotation, Ship, ShipResources, Stats, Wrapping,
+};
+use crate::core::util::random_bearing;
+use crate::settings::Settings;
+use glam::Vec2;
+use rand::Rng;
+use specs::{
+    Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
+};
+
+pub struct ExplodeShipsSystem;
+
+/// Damage ships that have collided with something, destroying those with no hit points left.
+///
+/// The ship lives on its pilot's entity, so rather than deleting the entity we strip the
+/// ship's components from it and start the pilot's respawn timer.
+///
+/// Flying into a power-up doesn't hurt, and invulnerable ships can't be damaged at all.
+impl<'s> System<'s> for ExplodeShipsSystem {
+    type SystemData = (
+        ReadStorage<'s, Collision>,
+        ReadStorage<'s, Ship>,
+        ReadStorage<'s, Position>,
+        ReadStorage<'s, LinearVelocity>,
+        ReadStorage<'s, Roid>,
+        ReadStorage<'s, Bullet>,
+        ReadStorage<'s, PowerUp>,
+        ReadStorage<'s, Invulnerable>,
+        WriteStorage<'s, Health>,
+        WriteStorage<'s, Pilot>,
+        WriteStorage<'s, Stats>,
+        Entities<'s>,
+        ReadExpect<'s, Settings>,
+        WriteExpect<'s, GameRng>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            collisions,
+            ships,
+            positions,
+            linear_velocities,
+            roids,
+            bullets,
+            power_ups,
+            invulnerable,
+            mut healths,
+            mut pilots,
+            mut stats,
+            entities,
+            settings,
+            mut rng,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        let rng = &mut rng.0;
+
+        for (collision, _ship, position, entity) in
+            (&collisions, &ships, &positions, &entities).join()
+        {
+            let only_power_ups =
+                !collision.with.is_empty() && collision.with.iter().all(|e| power_ups.contains(*e));
+            if only_power_ups || invulnerable.contains(entity) {
+                lazy.remove::<Collision>(entity);
+                continue;
+            }
+
+            // Ramming something hurts more the bigger it is and the faster it's going relative
+            // to the ship.
+            let velocity = linear_velocities.get(entity).map_or(Vec2::ZERO, |v| v.0);
+            let impact = |other| {
+                let other_velocity = linear_velocities.get(other).map_or(Vec2::ZERO, |v| v.0);
+                (velocity - other_velocity).length() * settings.collision_damage
+            };
+            let damage: f32 = collision
+                .with
+                .iter()
+                .map(|other| {
+                    if let Some(roid) = roids.get(*other) {
+                        roid.radius * impact(*other)
+                    } else if let Some(other_ship) = ships.get(*other) {
+                        other_ship.length / 2.0 * impact(*other)
+                    } else if bullets.contains(*other) {
+                        settings.bullet_damage
+                    } else {
+                        0.0
+                    }
+                })
+                .sum();
+
+            // Ships without hit points go down in one hit
+            let destroyed = healths
+                .get_mut(entity)
+                .is_none_or(|health| health.damage(damage));
+            if !destroyed {
+                lazy.remove::<Collision>(entity);
+                continue;
+            }
+
+            // In PvP games whoever shot the ship down gets the credit, once however many of
+            // their shots hit it.
+            let mut shooters: Vec<_> = collision
+                .with
+                .iter()
+                .filter_map(|e| bullets.get(*e))
+                .map(|bullet| bullet.owner)
+                .collect();
+            shooters.sort();
+            shooters.dedup();
+            for shooter in shooters {
+                if let Some(stats) = stats.get_mut(shooter) {
+                    stats.score += settings.ship_points;
+                    stats.kills += 1;
+                }
+            }
+
+            if let Some(pilot) = pilots.get_mut(entity) {
+                pilot.lives = pilot.lives.saturating_sub(1);
+            }
+            if let Some(stats) = stats.get_mut(entity) {
+                stats.deaths += 1;
+            }
+
+            lazy.remove::<Ship>(entity);
+            lazy.remove::<ShipResources>(entity);
+            lazy.remove::<Health>(entity);
+            lazy.remove::<PowerUpEffects>(entity);
+            lazy.remove::<Invulnerable>(entity);
+            lazy.remove::<Position>(entity);
+            lazy.remove::<Rotation>(entity);
+            lazy.remove::<LinearVelocity>(entity);
+            lazy.remove::<AngularVelocity>(entity);
+            lazy.remove::<Wrapping>(entity);
+            lazy.remove::<CollisionHandle>(entity);
+            lazy.remove::<Collision>(entity);
+            lazy.insert(entity, RespawnTimer(settings.ship_respawn_delay));
+
+            for _ in 0..(rng.random::<u32>() % 10 + 15) {
+                let new_entity = entities.create();
+
+                make_fragment(
+                    specs::world::LazyBuilder {
+                        entity: new_entity,
+                        lazy: &lazy,
+                    },
+                    position.0.x,
+                    position.0.y,
+                    random_bearing(rng),
+                    rng,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Cannon, Fragment, Pilot, Weapon};
+    use specs::{Builder, RunNow, World, WorldExt};
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Collision>();
+        world.register::<Ship>();
+        world.register::<ShipResources>();
+        world.register::<Position>();
+        world.register::<Roid>();
+        world.register::<Health>();
+        world.register::<Bullet>();
+        world.register::<PowerUp>();
+        world.register::<PowerUpEffects>();
+        world.register::<Invulnerable>();
+        world.register::<Pilot>();
+        world.register::<Stats>();
+        world.register::<RespawnTimer>();
+        world.register::<Fragment>();
+        world.register::<Rotation>();
+        world.register::<LinearVelocity>();
+        world.register::<AngularVelocity>();
+        world.register::<Wrapping>();
+        world.register::<CollisionHandle>();
+        world.insert(Settings::load().expect("settings"));
+        world.insert(GameRng::from_seed(1));
+        world
+    }
+
+    fn ship() -> Ship {
+        Ship::new(
+            10.0,
+            5.0,
+            1.0,
+            1.0,
+            1.0,
+            Cannon {
+                bullet_speed: 1.0,
+                rate_of_fire: 1.0,
+                weapon: Weapon::Gun,
+            },
+        )
+    }
+
+    #[test]
+    fn strips_ship_and_starts_respawn_timer() {
+        let mut world = world();
+        let delay = world.read_resource::<Settings>().ship_respawn_delay;
+
+        let pilot = world
+            .create_entity()
+            .with(Pilot::new("pilot", 3))
+            .with(Stats::default())
+            .with(ship())
+            .with(Position(glam::Vec2::new(1.0, 2.0)))
+            .with(Collision::default())
+            .build();
+
+        let mut system = ExplodeShipsSystem;
+        system.run_now(&world);
+        world.maintain();
+
+        assert!(world.entities().is_alive(pilot));
+        assert!(world.read_storage::<Ship>().get(pilot).is_none());
+        assert!(world.read_storage::<Collision>().get(pilot).is_none());
+        assert_eq!(
+            world.read_storage::<Pilot>().get(pilot).map(|p| p.lives),
+            Some(2)
+        );
+        assert_eq!(
+            world.read_storage::<Stats>().get(pilot).map(|s| s.deaths),
+            Some(1)
+        );
+        let timer = world.read_storage::<RespawnTimer>().get(pilot).copied();
+        assert_eq!(timer.map(|t| t.0), Some(delay));
+        assert!(world.read_storage::<Fragment>().count() > 0);
+    }
+
+    #[test]
+    fn shooter_is_credited_with_the_kill() {
+        let mut world = world();
+        let points = world.read_resource::<Settings>().ship_points;
+
+        let shooter = world
+            .create_entity()
+            .with(Pilot::new("shooter", 3))
+            .with(Stats::default())
+            .build();
+        let bullet = world
+            .create_entity()
+            .with(Bullet::new(shooter, Weapon::Gun))
+            .build();
+        world
+            .create_entity()
+            .with(Pilot::new("target", 3))
+            .with(Stats::default())
+            .with(ship())
+            .with(Position(glam::Vec2::new(1.0, 2.0)))
+            .with(Collision { with: vec![bullet] })
+            .build();
+
+        ExplodeShipsSystem.run_now(&world);
+        world.maintain();
+
+        let stats = world.read_storage::<Stats>();
+        let shooter_stats = stats.get(shooter).expect("stats");
+        assert_eq!(shooter_stats.kills, 1);
+        assert_eq!(shooter_stats.score, points);
+    }
+
+    #[test]
+    fn several_hits_from_one_shooter_count_once() {
+        let mut world = world();
+        let points = world.read_resource::<Settings>().ship_points;
+
+        let shooter = world
+            .create_entity()
+            .with(Pilot::new("shooter", 3))
+            .with(Stats::default())
+            .build();
+        let bullets = vec![
+            world
+                .create_entity()
+                .with(Bullet::new(shooter, Weapon::Spread))
+                .build(),
+            world
+                .create_entity()
+                .with(Bullet::new(shooter, Weapon::Spread))
+                .build(),
+        ];
+        world
+            .create_entity()
+            .with(Pilot::new("target", 3))
+            .with(Stats::default())
+            .with(ship())
+            .with(Position(glam::Vec2::new(1.0, 2.0)))
+            .with(Collision { with: bullets })
+            .build();
+
+        ExplodeShipsSystem.run_now(&world);
+        world.maintain();
+
+        let stats = world.read_storage::<Stats>();
+        let shooter_stats = stats.get(shooter).expect("stats");
+        assert_eq!(shooter_stats.kills, 1);
+        assert_eq!(shooter_stats.score, points);
+    }
+
+    #[test]
+    fn shielded_ships_survive() {
+        let mut world = world();
+        let pilot = world
+            .create_entity()
+            .with(Pilot::new("pilot", 3))
+            .with(Stats::default())
+            .with(ship())
+            .with(Position(glam::Vec2::new(1.0, 2.0)))
+            .with(Invulnerable(1.0))
+            .with(Collision::default())
+            .build();
+
+        ExplodeShipsSystem.run_now(&world);
+        world.maintain();
+
+        assert!(world.read_storage::<Ship>().get(pilot).is_some());
+        assert!(world.read_storage::<Collision>().get(pilot).is_none());
+    }
+
+    #[test]
+    fn damage_depends_on_roid_size_and_speed() {
+        let mut world = world();
+        let collision_damage = world.read_resource::<Settings>().collision_damage;
+        let health = Health {
+            hit_points: 100.0,
+            max_hit_points: 100.0,
+            shield: 0.0,
+            max_shield: 0.0,
+        };
+
+        // A slow roid scrapes the ship, and a fast one finishes it off
+        let ship_with_roid = |world: &mut World, speed: f32| {
+            let roid = world
+                .create_entity()
+                .with(Roid {
+                    radius: 20.0,
+                    points: vec![],
+                })
+                .with(LinearVelocity(Vec2::new(speed, 0.0)))
+                .build();
+            world
+                .create_entity()
+                .with(ship())
+                .with(health.clone())
+                .with(Position(Vec2::new(1.0, 2.0)))
+                .with(LinearVelocity(Vec2::ZERO))
+                .with(Collision { with: vec![roid] })
+                .build()
+        };
+        let slow = ship_with_roid(&mut world, 50.0);
+        let fast = ship_with_roid(&mut world, 100.0 / (20.0 * collision_damage));
+
+        ExplodeShipsSystem.run_now(&world);
+        world.maintain();
+
+        let healths = world.read_storage::<Health>();
+        let expected = 100.0 - 20.0 * 50.0 * collision_damage;
+        assert!((healths.get(slow).unwrap().hit_points - expected).abs() < 0.001);
+        assert!(world.read_storage::<Ship>().get(slow).is_some());
+        assert!(world.read_storage::<Collision>().get(slow).is_none());
+        assert!(world.read_storage::<Ship>().get(fast).is_none());
+    }
+}
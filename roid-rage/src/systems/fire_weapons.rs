@@ -0,0 +1,358 @@
+use crate::components::{
+    make_bullet, make_mine, weapon_collision_groups, Bullet, Collision, Firing, LaserBeam, Missile,
+    Pilot, Position, PowerUpEffects, Rotation, Ship, Stats, TimeDelta, Weapon,
+};
+use crate::core::field::Field;
+use crate::settings::Settings;
+use ncollide2d::na::{Point2, Vector2};
+use ncollide2d::query::Ray;
+use ncollide2d::world::CollisionWorld;
+use specs::{
+    Builder, Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, WorldExt,
+    WriteExpect, WriteStorage,
+};
+use sted::to_vector;
+
+pub struct FireWeaponsSystem;
+
+/// Fire the weapon of every ship whose pilot pulled the trigger this tick.
+///
+/// Lasers hit instantly, so rather than creating a bullet we look along the beam for the first
+/// thing it hits and record a collision with it, to be dealt with like any other next tick.
+impl<'s> System<'s> for FireWeaponsSystem {
+    type SystemData = (
+        WriteStorage<'s, Firing>,
+        ReadStorage<'s, Ship>,
+        ReadStorage<'s, Position>,
+        ReadStorage<'s, Rotation>,
+        ReadStorage<'s, PowerUpEffects>,
+        ReadStorage<'s, Pilot>,
+        WriteStorage<'s, Stats>,
+        WriteStorage<'s, LaserBeam>,
+        Entities<'s>,
+        Read<'s, TimeDelta>,
+        ReadExpect<'s, Field<f32>>,
+        WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
+        ReadExpect<'s, Settings>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut firing,
+            ships,
+            positions,
+            rotations,
+            power_up_effects,
+            pilots,
+            mut stats,
+            mut laser_beams,
+            entities,
+            time_delta,
+            field,
+            mut collision_world,
+            settings,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        // Fade out the beams of earlier laser shots
+        for (beam, entity) in (&mut laser_beams, &entities).join() {
+            beam.time_left -= time_delta.0.as_secs_f32();
+            if beam.time_left <= 0.0 {
+                if let Err(e) = entities.delete(entity) {
+                    println!("Error deleting laser beam: {}", e);
+                }
+            }
+        }
+
+        for (_, ship, position, rotation, entity) in
+            (&firing, &ships, &positions, &rotations, &entities).join()
+        {
+            let heading = to_vector(rotation.0);
+            let firing_position = position.0 + heading * (ship.length / 2.0);
+            let weapon = ship.cannon.weapon;
+
+            match weapon {
+                Weapon::Laser => {
+                    if let Some(stats) = stats.get_mut(entity) {
+                        stats.shots_fired += 1;
+                    }
+
+                    // As with bullets, lasers never hit the ship that fired them, nor a
+                    // team-mate's ship unless friendly fire is on.
+                    let ray = Ray::new(
+                        Point2::new(firing_position.x, firing_position.y),
+                        Vector2::new(heading.x, heading.y),
+                    );
+                    let target = laser_target(
+                        &ray,
+                        &entities,
+                        &field,
+                        &collision_world,
+                        &settings,
+                        |target| {
+                            target != entity
+                                && match (pilots.get(entity), pilots.get(target)) {
+                                    (Some(shooter), Some(target)) => {
+                                        settings.friendly_fire || !shooter.is_teammate(target)
+                                    }
+                                    _ => true,
+                                }
+                        },
+                    );
+                    let range = target.map_or(settings.laser_range, |(_, distance)| distance);
+
+                    let beam = entities.create();
+                    lazy.insert(beam, Bullet::new(entity, Weapon::Laser));
+                    lazy.insert(
+                        beam,
+                        LaserBeam {
+                            start: firing_position,
+                            end: firing_position + heading * range,
+                            time_left: LaserBeam::duration(),
+                        },
+                    );
+
+                    if let Some((target, _)) = target {
+                        if let Some(stats) = stats.get_mut(entity) {
+                            stats.hits += 1;
+                        }
+
+                        // Other collisions this tick may still be waiting to be cleared up, so
+                        // record the hit after they are.
+                        lazy.exec_mut(move |world| {
+                            match world.write_storage::<Collision>().entry(target) {
+                                Ok(entry) => {
+                                    entry.or_insert_with(Collision::default).with.push(beam)
+                                }
+                                Err(e) => println!("Error recording laser hit: {}", e),
+                            }
+                        });
+                    }
+                }
+                Weapon::Mine => {
+                    if let Some(stats) = stats.get_mut(entity) {
+                        stats.shots_fired += 1;
+                    }
+
+                    // Mines are dropped behind the ship
+                    let new_entity = entities.create();
+                    make_mine(
+                        specs::world::LazyBuilder {
+                            entity: new_entity,
+                            lazy: &lazy,
+                        },
+                        entity,
+                        position.0 - heading * (ship.length / 2.0),
+                        settings.mine_arm_delay,
+                        settings.mine_lifetime,
+                        &mut collision_world,
+                    );
+                }
+                Weapon::Gun | Weapon::Spread | Weapon::Homing => {
+                    // The spread shot power-up turns each bullet into three
+                    let spread_shot = power_up_effects
+                        .get(entity)
+                        .is_some_and(|effects| effects.spread_shot > 0.0);
+                    let copies = if spread_shot {
+                        let angle = settings.spread_shot_angle;
+                        vec![-angle, 0.0, angle]
+                    } else {
+                        vec![0.0]
+                    };
+
+                    for angle in weapon.spread(&settings) {
+                        for copy in &copies {
+                            if let Some(stats) = stats.get_mut(entity) {
+                                stats.shots_fired += 1;
+                            }
+
+                            let velocity =
+                                to_vector(rotation.0 + angle + copy) * ship.cannon.bullet_speed;
+                            let builder = specs::world::LazyBuilder {
+                                entity: entities.create(),
+                                lazy: &lazy,
+                            };
+                            let builder = if weapon == Weapon::Homing {
+                                builder.with(Missile {
+                                    time_left: settings.missile_lifetime,
+                                })
+                            } else {
+                                builder
+                            };
+                            make_bullet(
+                                builder,
+                                entity,
+                                weapon,
+                                firing_position,
+                                velocity,
+                                settings.pvp,
+                                &mut collision_world,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        firing.clear();
+    }
+}
+
+/// The first thing along `ray` that `can_hit` allows a laser to hit, and how far away it is.
+///
+/// Beams wrap around the edges of the field, so we also look along the beam from each image of
+/// its origin in the neighbouring copies of the field.
+fn laser_target(
+    ray: &Ray<f32>,
+    entities: &Entities,
+    field: &Field<f32>,
+    collision_world: &CollisionWorld<f32, specs::world::Index>,
+    settings: &Settings,
+    can_hit: impl Fn(Entity) -> bool,
+) -> Option<(Entity, f32)> {
+    let collision_groups = weapon_collision_groups(settings.pvp);
+
+    field
+        .images(ray.origin.x, ray.origin.y)
+        .iter()
+        .flat_map(|(x, y)| {
+            let image = Ray::new(Point2::new(*x, *y), ray.dir);
+            collision_world
+                .interferences_with_ray(&image, settings.laser_range, &collision_groups)
+                .map(|(_, obj, intersection)| (entities.entity(*obj.data()), intersection.toi))
+                .collect::<Vec<_>>()
+        })
+        .filter(|(target, _)| entities.is_alive(*target) && can_hit(*target))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{
+        make_roid, AngularVelocity, Cannon, CollisionHandle, GameRng, LinearVelocity, Mine, Roid,
+        Wrapping,
+    };
+    use glam::Vec2;
+    use specs::{RunNow, World};
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Firing>();
+        world.register::<Ship>();
+        world.register::<Position>();
+        world.register::<Rotation>();
+        world.register::<PowerUpEffects>();
+        world.register::<Pilot>();
+        world.register::<Stats>();
+        world.register::<LaserBeam>();
+        world.register::<Bullet>();
+        world.register::<Missile>();
+        world.register::<Mine>();
+        world.register::<Collision>();
+        world.register::<Roid>();
+        world.register::<LinearVelocity>();
+        world.register::<AngularVelocity>();
+        world.register::<Wrapping>();
+        world.register::<CollisionHandle>();
+        let settings = Settings::load().expect("settings");
+        world.insert(Field::new(settings.screen_width, settings.screen_height));
+        world.insert(settings);
+        world.insert(TimeDelta::default());
+        world.insert(CollisionWorld::<f32, specs::world::Index>::new(0.02f32));
+        world
+    }
+
+    /// A ship at the origin, facing east and about to fire `weapon`.
+    fn armed_ship(world: &mut World, weapon: Weapon) -> Entity {
+        let mut ship = Ship::from_settings(&world.read_resource::<Settings>());
+        ship.cannon = Cannon::with_weapon(weapon, &world.read_resource::<Settings>());
+        world
+            .create_entity()
+            .with(ship)
+            .with(Position(Vec2::ZERO))
+            .with(Rotation(0.0))
+            .with(Stats::default())
+            .with(Firing)
+            .build()
+    }
+
+    /// A still roid of radius 20 at (`x`, 0).
+    fn roid(world: &World, x: f32) -> Entity {
+        let mut collision_world =
+            world.write_resource::<CollisionWorld<f32, specs::world::Index>>();
+        let builder = world.create_entity_unchecked();
+        let entity = builder.entity;
+        make_roid(
+            builder,
+            x,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            20.0,
+            0.0,
+            &mut GameRng::from_seed(1).0,
+            &mut collision_world,
+        );
+        collision_world.update();
+        entity
+    }
+
+    #[test]
+    fn laser_hits_the_first_roid_in_range() {
+        let mut world = world();
+        let shooter = armed_ship(&mut world, Weapon::Laser);
+        let roid = roid(&world, 100.0);
+
+        FireWeaponsSystem.run_now(&world);
+        world.maintain();
+
+        let collisions = world.read_storage::<Collision>();
+        let beam = collisions.get(roid).expect("roid is hit").with[0];
+        let beams = world.read_storage::<LaserBeam>();
+        let beam = beams.get(beam).expect("laser beam");
+        assert!((beam.end.x - 80.0).abs() < 0.1);
+
+        let stats = world.read_storage::<Stats>();
+        let stats = stats.get(shooter).expect("stats");
+        assert_eq!((stats.shots_fired, stats.hits), (1, 1));
+        assert!(world.read_storage::<Firing>().is_empty());
+    }
+
+    #[test]
+    fn laser_wraps_around_the_field() {
+        let mut world = world();
+        let width = world.read_resource::<Settings>().screen_width;
+        let shooter = armed_ship(&mut world, Weapon::Laser);
+        world
+            .write_storage::<Position>()
+            .insert(shooter, Position(Vec2::new(width - 20.0, 0.0)))
+            .unwrap();
+        let roid = roid(&world, 100.0);
+
+        FireWeaponsSystem.run_now(&world);
+        world.maintain();
+
+        let collisions = world.read_storage::<Collision>();
+        let beam = collisions.get(roid).expect("roid is hit").with[0];
+        let beams = world.read_storage::<LaserBeam>();
+        let beam = beams.get(beam).expect("laser beam");
+        assert!((beam.end.x - (width + 80.0)).abs() < 0.1);
+    }
+
+    #[test]
+    fn spread_fires_several_bullets() {
+        let mut world = world();
+        let count = world.read_resource::<Settings>().spread_bullet_count;
+        armed_ship(&mut world, Weapon::Spread);
+
+        FireWeaponsSystem.run_now(&world);
+        world.maintain();
+
+        assert_eq!(world.read_storage::<Bullet>().count() as u32, count);
+        assert!(world.read_storage::<LaserBeam>().is_empty());
+    }
+}
@@ -1,27 +1,45 @@
 pub use self::age_fragments::AgeFragmentsSystem;
+pub use self::age_power_ups::AgePowerUpsSystem;
+pub use self::arm_mines::ArmMinesSystem;
 pub use self::cleanup_collisions::CleanupCollisionsSystem;
+pub use self::collect_power_ups::CollectPowerUpsSystem;
 pub use self::detect_collisions::DetectCollisionsSystem;
+pub use self::end_game::EndGameSystem;
 pub use self::explode_bullets::ExplodeBulletsSystem;
 pub use self::explode_roids::ExplodeRoidsSystem;
+pub use self::explode_ships::ExplodeShipsSystem;
+pub use self::fire_weapons::FireWeaponsSystem;
 pub use self::move_objects::MoveObjectsSystem;
 pub use self::register_pilots::PilotRegistrationSystem;
 pub use self::query_pilot::QueryPilotSystem;
+pub use self::recharge_shields::RechargeShieldsSystem;
 pub use self::remove_out_of_bounds::RemoveOutOfBoundsSystem;
+use self::remove_pilot::remove_pilot;
 pub use self::repopulate::RepopulateSystem;
 pub use self::respawn_ship::RespawnShipSystem;
+pub use self::steer_missiles::SteerMissilesSystem;
 pub use self::wrap_objects::WrapObjectsSystem;
 
 mod age_fragments;
+mod age_power_ups;
+mod arm_mines;
 mod cleanup_collisions;
+mod collect_power_ups;
 mod detect_collisions;
+mod end_game;
 mod explode_bullets;
 mod explode_roids;
+mod explode_ships;
+mod fire_weapons;
 mod move_objects;
 mod register_pilots;
 mod query_pilot;
+mod recharge_shields;
 mod remove_out_of_bounds;
+mod remove_pilot;
 mod repopulate;
 mod respawn_ship;
+mod steer_missiles;
 mod wrap_objects;
 
 #[cfg(test)]
@@ -62,6 +80,6 @@ mod tests {
 
         let positions = world.read_storage::<Position>();
         let position = (&positions).join().next().unwrap();
-        assert_eq!(position.0, glam::Vec2::new(0.0, 5.0));
+        assert_eq!(position.0, glam::Vec2::new(1.5, 5.0));
     }
 }
@@ -1,17 +1,20 @@
-/// This queries the pilot process using grpc to figure out
-/// what it wants to do, e.g. shoot, turn, etc.
+/// This streams the game state to each pilot process using grpc and
+/// applies what it wants to do, e.g. shoot, turn, etc.
+use super::remove_pilot;
 use crate::components::{
-    make_bullet, AngularVelocity, Bullet, FireTimer, LinearVelocity, Pilot, Position, Roid,
-    Rotation, Ship, TimeDelta,
+    AngularVelocity, Bullet, Cannon, DepartedPilots, FireTimer, Firing, Fragment, Health,
+    Invulnerable, LaserBeam, LinearVelocity, Mine, Pilot, Position, PowerUp, PowerUpEffects, Roid,
+    Rotation, Ship, ShipResources, Stats, TimeDelta, Weapon,
 };
 use crate::core::field::Field;
+use crate::core::util::entity_id;
+use crate::pilot_connection::PilotConnection;
+use crate::replay::ReplayRecorder;
 use crate::settings::Settings;
-use ncollide2d::world::CollisionWorld;
 use roid_rage_grpc::roid_rage as rpc;
-use specs::{
-    Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
-};
+use specs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage};
 use std::convert::TryFrom;
+use std::time::{Duration, Instant};
 use sted::to_vector;
 
 pub struct QueryPilotSystem;
@@ -23,51 +26,128 @@ impl QueryPilotSystem {
 }
 
 /// Identify target and shoot a bullet
+///
+/// Ships whose pilots fire are marked as `Firing`, for `FireWeaponsSystem` to fire their weapons.
 impl<'s> System<'s> for QueryPilotSystem {
     type SystemData = (
         ReadStorage<'s, Roid>,
-        ReadStorage<'s, Pilot>,
-        ReadStorage<'s, Ship>,
+        WriteStorage<'s, Pilot>,
+        WriteStorage<'s, Ship>,
+        WriteStorage<'s, ShipResources>,
         WriteStorage<'s, LinearVelocity>,
         WriteStorage<'s, AngularVelocity>,
         WriteStorage<'s, FireTimer>,
+        WriteStorage<'s, Firing>,
+        WriteStorage<'s, Stats>,
         ReadStorage<'s, Position>,
         ReadStorage<'s, Rotation>,
-        WriteStorage<'s, Bullet>,
+        ReadStorage<'s, Bullet>,
+        ReadStorage<'s, Fragment>,
+        ReadStorage<'s, PowerUp>,
+        ReadStorage<'s, PowerUpEffects>,
+        ReadStorage<'s, Invulnerable>,
+        ReadStorage<'s, Health>,
+        ReadStorage<'s, LaserBeam>,
+        ReadStorage<'s, Mine>,
         ReadExpect<'s, Field<f32>>,
         Read<'s, TimeDelta>,
         ReadExpect<'s, tokio::runtime::Runtime>,
         Entities<'s>,
-        WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
         ReadExpect<'s, Settings>,
-        Read<'s, LazyUpdate>,
+        Write<'s, Option<ReplayRecorder>>,
+        Write<'s, DepartedPilots>,
     );
 
     fn run(
         &mut self,
         (
             roids,
-            pilots,
-            ships,
+            mut pilots,
+            mut ships,
+            mut ship_resources,
             mut linear_velocities,
             mut angular_velocities,
             mut fire_timers,
+            mut firing,
+            mut stats,
             positions,
             rotations,
-            _bullets,
+            bullets,
+            fragments,
+            power_ups,
+            power_up_effects,
+            invulnerable,
+            healths,
+            laser_beams,
+            mines,
             field,
             time_delta,
             runtime,
             entities,
-            mut collision_world,
             settings,
-            lazy,
+            mut recorder,
+            mut departed,
         ): Self::SystemData,
     ) {
-        let roids: Vec<rpc::Roid> = (&roids, &linear_velocities, &positions)
+        let roids: Vec<rpc::Roid> = (
+            &entities,
+            &roids,
+            &linear_velocities,
+            &positions,
+            &rotations,
+        )
+            .join()
+            .map(
+                |(entity, roid, linear_velocity, position, rotation)| rpc::Roid {
+                    id: entity_id(entity),
+                    radius: roid.radius,
+                    position: Some(rpc::Position {
+                        x: position.0.x,
+                        y: position.0.y,
+                    }),
+                    velocity: Some(rpc::Velocity {
+                        x: linear_velocity.0.x,
+                        y: linear_velocity.0.y,
+                    }),
+                    outline: roid
+                        .outline()
+                        .iter()
+                        .map(|p| rpc::Position { x: p.x, y: p.y })
+                        .collect(),
+                    rotation: rotation.0,
+                },
+            )
+            .collect();
+
+        let ship_states: Vec<rpc::Ship> = (
+            &entities,
+            &ships,
+            &positions,
+            &rotations,
+            &linear_velocities,
+        )
+            .join()
+            .map(
+                |(entity, ship, position, rotation, linear_velocity)| rpc::Ship {
+                    health: healths.get(entity).map(Health::to_rpc),
+                    ..ship_state(
+                        entity,
+                        ship,
+                        ship_resources.get(entity),
+                        effects_state(entity, &power_up_effects, &invulnerable),
+                        position,
+                        rotation,
+                        linear_velocity,
+                    )
+                },
+            )
+            .collect();
+
+        let bullet_states: Vec<rpc::Bullet> = (&entities, &bullets, &linear_velocities, &positions)
             .join()
-            .map(|(roid, linear_velocity, position)| rpc::Roid {
-                radius: roid.radius,
+            .map(|(entity, bullet, linear_velocity, position)| rpc::Bullet {
+                id: entity_id(entity),
+                owner: entity_id(bullet.owner),
                 position: Some(rpc::Position {
                     x: position.0.x,
                     y: position.0.y,
@@ -76,33 +156,79 @@ impl<'s> System<'s> for QueryPilotSystem {
                     x: linear_velocity.0.x,
                     y: linear_velocity.0.y,
                 }),
+                weapon: bullet.weapon.to_rpc() as i32,
             })
             .collect();
 
-        let mut disconnected = Vec::new();
+        let laser_beam_states: Vec<rpc::LaserBeam> = (&bullets, &laser_beams)
+            .join()
+            .map(|(bullet, beam)| rpc::LaserBeam {
+                owner: entity_id(bullet.owner),
+                start: Some(rpc::Position {
+                    x: beam.start.x,
+                    y: beam.start.y,
+                }),
+                end: Some(rpc::Position {
+                    x: beam.end.x,
+                    y: beam.end.y,
+                }),
+            })
+            .collect();
 
-        for (
-            entity,
-            pilot,
-            ship,
-            fire_timer,
-            position,
-            rotation,
-            linear_velocity,
-            angular_velocity,
-        ) in (
+        let fragments: Vec<rpc::Fragment> = (&fragments, &linear_velocities, &positions)
+            .join()
+            .map(|(_, linear_velocity, position)| rpc::Fragment {
+                position: Some(rpc::Position {
+                    x: position.0.x,
+                    y: position.0.y,
+                }),
+                velocity: Some(rpc::Velocity {
+                    x: linear_velocity.0.x,
+                    y: linear_velocity.0.y,
+                }),
+            })
+            .collect();
+
+        let power_up_states: Vec<rpc::PowerUp> = (&entities, &power_ups, &positions)
+            .join()
+            .map(|(entity, power_up, position)| rpc::PowerUp {
+                id: entity_id(entity),
+                kind: power_up.kind.to_rpc() as i32,
+                position: Some(rpc::Position {
+                    x: position.0.x,
+                    y: position.0.y,
+                }),
+                radius: PowerUp::radius(),
+                time_left: power_up.time_left,
+            })
+            .collect();
+
+        let field_state = rpc::Field {
+            width: field.width() as i32,
+            height: field.height() as i32,
+        };
+
+        // Send every pilot its game state first so that they all work on their commands while we
+        // wait for them.
+        for (entity, pilot, ship, fire_timer, stats, position, rotation, _) in (
             &entities,
             &pilots,
             &ships,
             &mut fire_timers,
+            &mut stats,
             &positions,
             &rotations,
-            &mut linear_velocities,
-            &mut angular_velocities,
+            &linear_velocities,
         )
             .join()
         {
-            fire_timer.0 += time_delta.0.as_secs_f32();
+            // Rapid fire reloads the cannon twice as fast
+            let rapid_fire = power_up_effects
+                .get(entity)
+                .is_some_and(|effects| effects.rapid_fire > 0.0);
+            let reload = if rapid_fire { 2.0 } else { 1.0 };
+            fire_timer.0 += time_delta.0.as_secs_f32() * reload;
+            stats.survival_time += time_delta.0.as_secs_f32();
 
             let ship_center = position.0;
 
@@ -110,95 +236,252 @@ impl<'s> System<'s> for QueryPilotSystem {
             let firing_position = ship_center + heading * (ship.length / 2.0);
 
             let game_state = rpc::GameState {
-                field: Some(rpc::Field {
-                    width: field.width() as i32,
-                    height: field.height() as i32,
-                }),
+                field: Some(field_state),
                 firing_position: Some(rpc::Position {
                     x: firing_position.x,
                     y: firing_position.y,
                 }),
                 // TODO: Is time_to_fire actually used?
-                time_to_fire: settings.rate_of_fire - fire_timer.0,
+                time_to_fire: ship.cannon.rate_of_fire - fire_timer.0,
                 roids: roids.clone(),
-                ship: Some(rpc::Ship {
-                    mass: ship.mass,
-                    thrust: ship.thrust,
-                    position: Some(rpc::Position {
-                        x: position.0.x,
-                        y: position.0.y,
-                    }),
-                    velocity: Some(rpc::Velocity {
-                        x: linear_velocity.0.x,
-                        y: linear_velocity.0.y,
-                    }),
-                    heading: rotation.0,
-                    cannon: Some(rpc::Cannon {
-                        bullet_speed: ship.cannon.bullet_speed,
-                        rate_of_fire: ship.cannon.rate_of_fire,
-                    }),
-                }),
+                ship: ship_states
+                    .iter()
+                    .find(|state| state.id == entity_id(entity))
+                    .cloned(),
+                other_ships: ship_states
+                    .iter()
+                    .filter(|other| other.id != entity_id(entity))
+                    .cloned()
+                    .collect(),
+                bullets: bullet_states.clone(),
+                fragments: fragments.clone(),
+                power_ups: power_up_states.clone(),
+                laser_beams: laser_beam_states.clone(),
+                stats: Some(stats.to_rpc(pilot)),
             };
 
-            // Pass game-state to pilot process
-            let res = runtime.block_on(query_pilot(pilot.url.to_string(), game_state));
+            if let Some(connection) = &pilot.connection {
+                connection.send(game_state);
+            }
+        }
+
+        // All pilots share one deadline, so a tick never waits longer than that no matter how
+        // many pilots there are.
+        let deadline = Instant::now() + Duration::from_millis(settings.pilot_command_deadline_ms);
 
-            match res {
-                Err(msg) => {
-                    println!("Error communicating with pilot {}: {:?}", pilot.url, msg);
+        let mut disconnected = Vec::new();
+        let mut commands = Vec::new();
+
+        for (entity, pilot, ship, fire_timer, stats, rotation, linear_velocity, angular_velocity) in
+            (
+                &entities,
+                &mut pilots,
+                &mut ships,
+                &mut fire_timers,
+                &mut stats,
+                &rotations,
+                &mut linear_velocities,
+                &mut angular_velocities,
+            )
+                .join()
+        {
+            let connection = match pilot.connection.as_mut() {
+                Some(connection) => connection,
+                None => continue,
+            };
+
+            let closed = connection.is_closed();
+            let command = if closed {
+                None
+            } else {
+                runtime.block_on(connection.next_command(deadline))
+            };
+
+            let command = match command {
+                Some(command) => {
+                    pilot.strikes = 0;
+                    pilot.last_command = Some(command);
+                    command
+                }
+                None if closed && !pilot.can_reconnect => {
+                    println!("Pilot {} hung up", pilot.url);
                     disconnected.push(entity);
+                    continue;
                 }
-                Ok(command) => {
-                    if apply_command(
-                        &command,
-                        ship,
-                        &rotation,
-                        linear_velocity,
-                        angular_velocity,
-                        fire_timer,
-                        &time_delta,
-                        &settings,
-                        &pilot.url,
-                    ) {
-                        let new_entity = entities.create();
-                        make_bullet(
-                            specs::world::LazyBuilder {
-                                entity: new_entity,
-                                lazy: &*lazy,
-                            },
-                            firing_position,
-                            heading * settings.bullet_speed,
-                            &mut collision_world,
+                None => {
+                    pilot.strikes += 1;
+                    if pilot.strikes >= settings.pilot_max_strikes {
+                        println!(
+                            "Disconnecting pilot {} after {} strikes",
+                            pilot.url, pilot.strikes
                         );
+                        disconnected.push(entity);
+                        continue;
                     }
+
+                    if closed {
+                        println!("Lost connection to pilot {}, reconnecting", pilot.url);
+                        pilot.connection = Some(PilotConnection::connect(&runtime, &pilot.url));
+                    } else {
+                        stats.deadline_misses += 1;
+                    }
+
+                    fallback_command(pilot.last_command, &settings)
+                }
+            };
+
+            commands.push(rpc::PilotCommand {
+                ship: entity_id(entity),
+                command: Some(command),
+            });
+
+            if apply_command(
+                &command,
+                ship,
+                ship_resources.get_mut(entity),
+                rotation,
+                linear_velocity,
+                angular_velocity,
+                fire_timer,
+                &time_delta,
+                &settings,
+                &pilot.url,
+            ) {
+                if let Err(e) = firing.insert(entity, Firing) {
+                    println!("Error firing cannon: {}", e);
                 }
             }
         }
 
+        if let Some(replay) = recorder.as_mut() {
+            let state = rpc::GameState {
+                field: Some(field_state),
+                roids,
+                bullets: bullet_states,
+                other_ships: ship_states,
+                fragments,
+                power_ups: power_up_states,
+                laser_beams: laser_beam_states,
+                ..Default::default()
+            };
+            let stats = (&pilots, &stats)
+                .join()
+                .map(|(pilot, stats)| stats.to_rpc(pilot))
+                .collect();
+            let ship_colors = (&entities, &pilots)
+                .join()
+                .filter_map(|(entity, pilot)| {
+                    pilot.color.map(|color| rpc::ShipColor {
+                        ship: entity_id(entity),
+                        color: Some(color),
+                    })
+                })
+                .collect();
+            let armed_mines = (&entities, &mines)
+                .join()
+                .filter(|(_, mine)| mine.is_armed())
+                .map(|(entity, _)| entity_id(entity))
+                .collect();
+
+            if let Err(err) = replay.record(state, commands, stats, ship_colors, armed_mines) {
+                println!("Error writing replay, recording stopped: {}", err);
+                *recorder = None;
+            }
+        }
+
         for entity in disconnected {
-            if let Err(err) = entities.delete(entity) {
-                println!("Failed to remove unresponsive pilot entity: {:?}", err);
+            if let (Some(pilot), Some(stats)) = (pilots.get(entity), stats.get(entity)) {
+                let final_stats = stats.to_rpc(pilot);
+                remove_pilot(entity, final_stats, &bullets, &entities, &mut departed);
             }
         }
     }
 }
 
-async fn query_pilot(
-    url: String,
-    game_state: rpc::GameState,
-) -> Result<rpc::Command, Box<dyn std::error::Error>> {
-    let mut client = rpc::pilot_client::PilotClient::connect(url).await?;
+/// Describe a ship to the pilots. Its health is left for the caller to fill in.
+fn ship_state(
+    entity: Entity,
+    ship: &Ship,
+    resources: Option<&ShipResources>,
+    effects: Option<rpc::PowerUpEffects>,
+    position: &Position,
+    rotation: &Rotation,
+    linear_velocity: &LinearVelocity,
+) -> rpc::Ship {
+    rpc::Ship {
+        id: entity_id(entity),
+        mass: ship.mass,
+        thrust: ship.thrust,
+        position: Some(rpc::Position {
+            x: position.0.x,
+            y: position.0.y,
+        }),
+        velocity: Some(rpc::Velocity {
+            x: linear_velocity.0.x,
+            y: linear_velocity.0.y,
+        }),
+        heading: rotation.0,
+        cannon: Some(rpc::Cannon {
+            bullet_speed: ship.cannon.bullet_speed,
+            rate_of_fire: ship.cannon.rate_of_fire,
+            weapon: ship.cannon.weapon.to_rpc() as i32,
+        }),
+        reverse_thrust: ship.reverse_thrust,
+        strafe_thrust: ship.strafe_thrust,
+        resources: resources.map(|resources| rpc::ShipResources {
+            fuel: resources.fuel,
+            fuel_capacity: resources.fuel_capacity,
+            heat: resources.heat,
+            heat_capacity: resources.heat_capacity,
+            energy: resources.energy,
+            energy_capacity: resources.energy_capacity,
+        }),
+        effects,
+        health: None,
+    }
+}
 
-    let request = tonic::Request::new(game_state);
+/// The time left on a ship's power-ups, if it has any.
+fn effects_state(
+    entity: Entity,
+    effects: &ReadStorage<PowerUpEffects>,
+    invulnerable: &ReadStorage<Invulnerable>,
+) -> Option<rpc::PowerUpEffects> {
+    let shield = invulnerable.get(entity).map_or(0.0, |i| i.0);
+    let effects = effects.get(entity).copied().unwrap_or_default();
+    if effects == PowerUpEffects::default() && shield <= 0.0 {
+        return None;
+    }
+    Some(rpc::PowerUpEffects {
+        rapid_fire: effects.rapid_fire,
+        spread_shot: effects.spread_shot,
+        shield,
+    })
+}
 
-    let response = client.get_command(request).await?;
+/// The command to use when a pilot doesn't answer in time, as chosen by the
+/// `pilot_fallback_command` setting: "null" does nothing, anything else repeats the last command.
+fn fallback_command(last_command: Option<rpc::Command>, settings: &Settings) -> rpc::Command {
+    match settings.pilot_fallback_command.as_str() {
+        "null" => rpc::Command::null(),
+        _ => last_command.unwrap_or_else(rpc::Command::null),
+    }
+}
 
-    Ok(response.get_ref().clone())
+/// An analog control clamped to its range, or `None` if it isn't a number.
+fn analog(value: f32, min: f32, max: f32, control: &str, pilot_url: &str) -> Option<f32> {
+    if value.is_nan() {
+        println!("Invalid {} {} from pilot {}", control, value, pilot_url);
+        None
+    } else {
+        Some(value.clamp(min, max))
+    }
 }
 
 fn apply_command(
     command: &rpc::Command,
-    ship: &Ship,
+    ship: &mut Ship,
+    mut resources: Option<&mut ShipResources>,
     rotation: &Rotation,
     linear_velocity: &mut LinearVelocity,
     angular_velocity: &mut AngularVelocity,
@@ -209,33 +492,84 @@ fn apply_command(
 ) -> bool {
     let heading = to_vector(rotation.0);
     let mut fired = false;
+    let dt = time_delta.0.as_secs_f32();
+
+    if let Some(resources) = resources.as_mut() {
+        resources.recover(dt, settings);
+    }
+
+    if let Some(weapon) = command.weapon {
+        match rpc::Weapon::try_from(weapon) {
+            Ok(weapon) => {
+                let weapon = Weapon::from_rpc(weapon);
+                // A new weapon starts its cooldown from scratch, so switching can't be used to
+                // fire sooner than its rate of fire allows.
+                if weapon != ship.cannon.weapon {
+                    ship.cannon = Cannon::with_weapon(weapon, settings);
+                    fire_timer.0 = 0.0;
+                }
+            }
+            Err(_) => println!("Invalid weapon {} from pilot {}", weapon, pilot_url),
+        }
+    }
 
-    if command.fire && fire_timer.0 >= settings.rate_of_fire {
+    let can_fire = resources
+        .as_ref()
+        .is_none_or(|resources| resources.can_fire(settings));
+    if command.fire && fire_timer.0 >= ship.cannon.rate_of_fire && can_fire {
         fire_timer.0 = 0.0;
         fired = true;
+        if let Some(resources) = resources.as_mut() {
+            resources.fire(settings);
+        }
     }
 
-    let rotation_direction = match rpc::Rotation::try_from(command.rotation) {
-        Ok(rpc::Rotation::Clockwise) => Some(1.0),
-        Ok(rpc::Rotation::Counterclockwise) => Some(-1.0),
-        Ok(rpc::Rotation::None) => Some(0.0),
-        Err(_) => {
-            println!(
-                "Invalid rotation value {} from pilot {}",
-                command.rotation, pilot_url
-            );
-            None
-        }
+    let rotation_direction = match command.turn_rate {
+        Some(turn_rate) => analog(turn_rate, -1.0, 1.0, "turn rate", pilot_url),
+        None => match rpc::Rotation::try_from(command.rotation) {
+            Ok(rpc::Rotation::Clockwise) => Some(1.0),
+            Ok(rpc::Rotation::Counterclockwise) => Some(-1.0),
+            Ok(rpc::Rotation::None) => Some(0.0),
+            Err(_) => {
+                println!(
+                    "Invalid rotation value {} from pilot {}",
+                    command.rotation, pilot_url
+                );
+                None
+            }
+        },
     };
 
     if let Some(rotation_direction) = rotation_direction {
         angular_velocity.0 = rotation_direction * ship.rotational_speed;
     }
 
-    if command.thrusters {
-        let steering_force = ship.thrust * heading;
-        let accel = steering_force / ship.mass;
-        linear_velocity.0 += accel * time_delta.0.as_secs_f32();
+    let thrust = match command.thrust {
+        Some(thrust) => analog(thrust, 0.0, 1.0, "thrust", pilot_url).unwrap_or(0.0),
+        None if command.thrusters => 1.0,
+        None => 0.0,
+    };
+    let reverse_thrust = command.reverse_thrust.map_or(0.0, |reverse_thrust| {
+        analog(reverse_thrust, 0.0, 1.0, "reverse thrust", pilot_url).unwrap_or(0.0)
+    });
+    let strafe = command.strafe.map_or(0.0, |strafe| {
+        analog(strafe, -1.0, 1.0, "strafe", pilot_url).unwrap_or(0.0)
+    });
+
+    // All the thrusters burn fuel in proportion to how hard they're pushing. If there isn't
+    // enough, they all get a share of what's left.
+    let burn = (thrust + reverse_thrust + strafe.abs()) * settings.ship_fuel_burn_rate * dt;
+    let available = resources
+        .as_mut()
+        .map_or(1.0, |resources| resources.burn(burn));
+
+    // Starboard is a quarter turn clockwise from the heading.
+    let starboard = glam::Vec2::new(-heading.y, heading.x);
+    let steering_force = heading * (thrust * ship.thrust - reverse_thrust * ship.reverse_thrust)
+        + starboard * (strafe * ship.strafe_thrust);
+    if steering_force != glam::Vec2::ZERO {
+        let accel = steering_force * available / ship.mass;
+        linear_velocity.0 += accel * dt;
     }
 
     fired
@@ -244,6 +578,7 @@ fn apply_command(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use specs::{World, WorldExt};
 
     fn settings() -> Settings {
         Settings::load().expect("settings")
@@ -256,13 +591,71 @@ mod tests {
             length: 1.0,
             width: 1.0,
             rotational_speed: 3.0,
+            reverse_thrust: 2.0,
+            strafe_thrust: 1.0,
             cannon: crate::components::Cannon {
                 bullet_speed: 10.0,
                 rate_of_fire: 0.5,
+                weapon: Weapon::Gun,
             },
         }
     }
 
+    #[test]
+    fn ship_state_describes_ship() {
+        let mut world = World::new();
+        let entity = world.entities().create();
+        world.maintain();
+
+        let state = ship_state(
+            entity,
+            &ship(),
+            None,
+            None,
+            &Position(glam::Vec2::new(1.0, 2.0)),
+            &Rotation(0.5),
+            &LinearVelocity(glam::Vec2::new(3.0, 4.0)),
+        );
+
+        assert_eq!(state.id, entity_id(entity));
+        assert_eq!(state.position, Some(rpc::Position { x: 1.0, y: 2.0 }));
+        assert_eq!(state.velocity, Some(rpc::Velocity { x: 3.0, y: 4.0 }));
+        assert_eq!(state.heading, 0.5);
+        assert_eq!(state.cannon.unwrap().bullet_speed, 10.0);
+    }
+
+    #[test]
+    fn fallback_command_repeats_last_command() {
+        let command = rpc::Command {
+            fire: true,
+            rotation: rpc::Rotation::Clockwise as i32,
+            thrusters: true,
+            ..rpc::Command::null()
+        };
+        let mut settings = settings();
+        settings.pilot_fallback_command = "repeat".to_string();
+
+        assert_eq!(fallback_command(Some(command), &settings), command);
+        assert_eq!(fallback_command(None, &settings), rpc::Command::null());
+    }
+
+    #[test]
+    fn fallback_command_can_be_null() {
+        let command = rpc::Command {
+            fire: true,
+            rotation: rpc::Rotation::Clockwise as i32,
+            thrusters: true,
+            ..rpc::Command::null()
+        };
+        let mut settings = settings();
+        settings.pilot_fallback_command = "null".to_string();
+
+        assert_eq!(
+            fallback_command(Some(command), &settings),
+            rpc::Command::null()
+        );
+    }
+
     #[test]
     fn apply_command_sets_rotation() {
         let mut linear_velocity = LinearVelocity(glam::Vec2::ZERO);
@@ -274,11 +667,13 @@ mod tests {
             fire: false,
             rotation: rpc::Rotation::Clockwise as i32,
             thrusters: false,
+            ..rpc::Command::null()
         };
 
         apply_command(
             &command,
-            &ship(),
+            &mut ship(),
+            None,
             &rotation,
             &mut linear_velocity,
             &mut angular_velocity,
@@ -302,11 +697,13 @@ mod tests {
             fire: false,
             rotation: 99,
             thrusters: false,
+            ..rpc::Command::null()
         };
 
         apply_command(
             &command,
-            &ship(),
+            &mut ship(),
+            None,
             &rotation,
             &mut linear_velocity,
             &mut angular_velocity,
@@ -330,11 +727,13 @@ mod tests {
             fire: false,
             rotation: rpc::Rotation::None as i32,
             thrusters: true,
+            ..rpc::Command::null()
         };
 
         apply_command(
             &command,
-            &ship(),
+            &mut ship(),
+            None,
             &rotation,
             &mut linear_velocity,
             &mut angular_velocity,
@@ -359,11 +758,13 @@ mod tests {
             fire: true,
             rotation: rpc::Rotation::None as i32,
             thrusters: false,
+            ..rpc::Command::null()
         };
 
         let fired = apply_command(
             &command,
-            &ship(),
+            &mut ship(),
+            None,
             &rotation,
             &mut linear_velocity,
             &mut angular_velocity,
@@ -376,4 +777,182 @@ mod tests {
         assert!(fired);
         assert!((fire_timer.0 - 0.0).abs() < 0.0001);
     }
+
+    /// Apply `command` to a stationary ship facing east, returning its linear and angular
+    /// velocity after half a second.
+    fn velocities_after(command: rpc::Command) -> (glam::Vec2, f32) {
+        let mut linear_velocity = LinearVelocity(glam::Vec2::ZERO);
+        let mut angular_velocity = AngularVelocity(0.0);
+        apply_command(
+            &command,
+            &mut ship(),
+            None,
+            &Rotation(0.0),
+            &mut linear_velocity,
+            &mut angular_velocity,
+            &mut FireTimer(0.0),
+            &TimeDelta(std::time::Duration::from_secs_f32(0.5)),
+            &settings(),
+            "pilot",
+        );
+        (linear_velocity.0, angular_velocity.0)
+    }
+
+    #[test]
+    fn analog_controls_take_precedence() {
+        let (linear, angular) = velocities_after(rpc::Command {
+            thrusters: true,
+            rotation: rpc::Rotation::Clockwise as i32,
+            thrust: Some(0.5),
+            turn_rate: Some(-0.5),
+            ..rpc::Command::null()
+        });
+
+        assert!((linear.x - 0.5).abs() < 0.0001);
+        assert!((angular + 1.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn analog_controls_are_clamped() {
+        let (linear, angular) = velocities_after(rpc::Command {
+            thrust: Some(5.0),
+            turn_rate: Some(f32::NAN),
+            ..rpc::Command::null()
+        });
+
+        assert!((linear.x - 1.0).abs() < 0.0001);
+        assert_eq!(angular, 0.0);
+    }
+
+    #[test]
+    fn reverse_and_side_thrusters() {
+        let (linear, _) = velocities_after(rpc::Command {
+            reverse_thrust: Some(1.0),
+            strafe: Some(-1.0),
+            ..rpc::Command::null()
+        });
+
+        // Backwards is west and port is north
+        assert!((linear.x + 0.5).abs() < 0.0001);
+        assert!((linear.y + 0.25).abs() < 0.0001);
+    }
+
+    #[test]
+    fn apply_command_is_limited_by_resources() {
+        let mut settings = settings();
+        settings.ship_heat_per_shot = 1.0;
+        let mut resources = ShipResources {
+            fuel: 0.0,
+            fuel_capacity: 10.0,
+            heat: 1.0,
+            heat_capacity: 1.5,
+            energy: 0.0,
+            energy_capacity: 0.0,
+        };
+        let mut linear_velocity = LinearVelocity(glam::Vec2::ZERO);
+        let command = rpc::Command {
+            fire: true,
+            thrusters: true,
+            ..rpc::Command::null()
+        };
+
+        let fired = apply_command(
+            &command,
+            &mut ship(),
+            Some(&mut resources),
+            &Rotation(0.0),
+            &mut linear_velocity,
+            &mut AngularVelocity(0.0),
+            &mut FireTimer(1.0),
+            &TimeDelta(std::time::Duration::from_secs_f32(0.1)),
+            &settings,
+            "pilot",
+        );
+
+        assert!(!fired);
+        assert_eq!(linear_velocity.0, glam::Vec2::ZERO);
+    }
+
+    #[test]
+    fn apply_command_selects_weapon() {
+        let settings = settings();
+        let mut ship = ship();
+        let command = rpc::Command {
+            fire: true,
+            weapon: Some(rpc::Weapon::Laser as i32),
+            ..rpc::Command::null()
+        };
+
+        // Ready to fire the gun, but not yet the slower laser
+        let fired = apply_command(
+            &command,
+            &mut ship,
+            None,
+            &Rotation(0.0),
+            &mut LinearVelocity(glam::Vec2::ZERO),
+            &mut AngularVelocity(0.0),
+            &mut FireTimer(settings.rate_of_fire),
+            &TimeDelta(std::time::Duration::from_secs_f32(0.0)),
+            &settings,
+            "pilot",
+        );
+
+        assert!(!fired);
+        assert_eq!(ship.cannon.weapon, Weapon::Laser);
+        assert_eq!(ship.cannon.rate_of_fire, settings.laser_rate_of_fire);
+        assert_eq!(ship.cannon.bullet_speed, 0.0);
+
+        let command = rpc::Command {
+            weapon: Some(99),
+            ..rpc::Command::null()
+        };
+        apply_command(
+            &command,
+            &mut ship,
+            None,
+            &Rotation(0.0),
+            &mut LinearVelocity(glam::Vec2::ZERO),
+            &mut AngularVelocity(0.0),
+            &mut FireTimer(0.0),
+            &TimeDelta(std::time::Duration::from_secs_f32(0.0)),
+            &settings,
+            "pilot",
+        );
+
+        assert_eq!(ship.cannon.weapon, Weapon::Laser);
+    }
+
+    #[test]
+    fn switching_weapons_restarts_the_cooldown() {
+        let settings = settings();
+        let mut ship = ship();
+        let mut fire_timer = FireTimer(100.0);
+        let mut select = |weapon: rpc::Weapon, fire_timer: &mut FireTimer| {
+            let command = rpc::Command {
+                fire: true,
+                weapon: Some(weapon as i32),
+                ..rpc::Command::null()
+            };
+            apply_command(
+                &command,
+                &mut ship,
+                None,
+                &Rotation(0.0),
+                &mut LinearVelocity(glam::Vec2::ZERO),
+                &mut AngularVelocity(0.0),
+                fire_timer,
+                &TimeDelta(std::time::Duration::from_secs_f32(0.0)),
+                &settings,
+                "pilot",
+            )
+        };
+
+        // Long past ready to fire anything, but the new weapon still has to cool down
+        assert!(!select(rpc::Weapon::Spread, &mut fire_timer));
+        assert_eq!(fire_timer.0, 0.0);
+
+        // Asking for the weapon already selected isn't a switch
+        fire_timer.0 = 100.0;
+        assert!(select(rpc::Weapon::Spread, &mut fire_timer));
+    }
 }
@@ -0,0 +1,21 @@
+use crate::components::{Health, TimeDelta};
+use crate::settings::Settings;
+use specs::{Join, Read, ReadExpect, System, WriteStorage};
+
+pub struct RechargeShieldsSystem;
+
+/// Recharge the shields of every ship.
+impl<'s> System<'s> for RechargeShieldsSystem {
+    type SystemData = (
+        WriteStorage<'s, Health>,
+        Read<'s, TimeDelta>,
+        ReadExpect<'s, Settings>,
+    );
+
+    fn run(&mut self, (mut healths, time_delta, settings): Self::SystemData) {
+        let dt = time_delta.0.as_secs_f32();
+        for health in (&mut healths).join() {
+            health.recharge(dt, &settings);
+        }
+    }
+}
@@ -1,22 +1,50 @@
-use crate::components::{FireTimer, Pilot};
+use super::remove_pilot;
+use crate::components::{Bullet, DepartedPilots, FireTimer, Pilot, Stats};
+use crate::core::util::entity_id;
+use crate::game_mode::GameMode;
+use crate::pilot_connection::PilotConnection;
 use crate::settings::Settings;
+use roid_rage_grpc::roid_rage as rpc;
 use roid_rage_grpc::roid_rage::pilot_registrar_server::{PilotRegistrar, PilotRegistrarServer};
-use roid_rage_grpc::roid_rage::{RegistrationRequest, RegistrationResponse};
+use roid_rage_grpc::roid_rage::{
+    game_message, pilot_message, GameMessage, PilotMessage, PilotStats, RegistrationRequest,
+    RegistrationResponse, StatsRequest, StatsResponse, UnregistrationRequest,
+    UnregistrationResponse, PROTOCOL_VERSION,
+};
 use specs::prelude::*;
 use specs::{Entities, System, World, WriteStorage};
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::Mutex;
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
 use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
-use tonic::{transport::Server, Code, Request, Response, Status};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{transport::Server, Code, Request, Response, Status, Streaming};
 
 // What needs to happen here?
 // 1. A task/thread for listening for pilot registrations
 
+/// A request from the registrar waiting for the game to deal with it.
+enum RegistrarMessage {
+    Register {
+        request: RegistrationRequest,
+        /// The connection, if the pilot opened it rather than waiting for us to.
+        connection: Option<PilotConnection>,
+        reply: oneshot::Sender<Result<RegistrationResponse, Status>>,
+    },
+    Unregister {
+        request: UnregistrationRequest,
+        reply: oneshot::Sender<Result<UnregistrationResponse, Status>>,
+    },
+}
+
 pub struct PilotRegistrationSystem {
-    rx: Receiver<String>,
-    tx: Sender<String>,
+    rx: Receiver<RegistrarMessage>,
+    tx: Sender<RegistrarMessage>,
+    /// Snapshot of every pilot's stats, shared with the registrar so it can serve `GetStats`.
+    stats: Arc<Mutex<Vec<PilotStats>>>,
     shutdown_tx: Option<oneshot::Sender<()>>,
     listener_task: Option<JoinHandle<Result<(), tonic::transport::Error>>>,
 }
@@ -28,6 +56,7 @@ impl PilotRegistrationSystem {
         PilotRegistrationSystem {
             rx: rx,
             tx: tx,
+            stats: Arc::new(Mutex::new(Vec::new())),
             shutdown_tx: None,
             listener_task: None,
         }
@@ -35,14 +64,28 @@ impl PilotRegistrationSystem {
 }
 
 impl<'s> System<'s> for PilotRegistrationSystem {
-    type SystemData = (WriteStorage<'s, Pilot>, WriteStorage<'s, FireTimer>, Entities<'s>);
+    type SystemData = (
+        WriteStorage<'s, Pilot>,
+        WriteStorage<'s, FireTimer>,
+        WriteStorage<'s, Stats>,
+        ReadStorage<'s, Bullet>,
+        Entities<'s>,
+        ReadExpect<'s, Settings>,
+        ReadExpect<'s, tokio::runtime::Runtime>,
+        Write<'s, DepartedPilots>,
+    );
     fn setup(&mut self, world: &mut World) {
         let runtime = world.read_resource::<tokio::runtime::Runtime>();
         let settings = world.read_resource::<Settings>();
         let addr = settings.pilot_registration_url;
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
         self.shutdown_tx = Some(shutdown_tx);
-        self.listener_task = Some(runtime.spawn(listen(addr, self.tx.clone(), shutdown_rx)));
+        self.listener_task = Some(runtime.spawn(listen(
+            addr,
+            self.tx.clone(),
+            self.stats.clone(),
+            shutdown_rx,
+        )));
     }
 
     fn dispose(self, _world: &mut World) {
@@ -54,36 +97,198 @@ impl<'s> System<'s> for PilotRegistrationSystem {
         }
     }
 
-    fn run(&mut self, (mut pilots, mut fire_timers, entities): Self::SystemData) {
-        loop {
-            match self.rx.try_recv() {
-                Err(_) => break,
-                Ok(pilot_url) => {
-                    if (&pilots).join().any(|pilot| pilot.url == pilot_url) {
-                        println!("duplicate pilot registration ignored: {}", pilot_url);
+    fn run(
+        &mut self,
+        (
+            mut pilots,
+            mut fire_timers,
+            mut stats,
+            bullets,
+            entities,
+            settings,
+            runtime,
+            mut departed,
+        ): Self::SystemData,
+    ) {
+        while let Ok(message) = self.rx.try_recv() {
+            match message {
+                RegistrarMessage::Register {
+                    request,
+                    connection,
+                    reply,
+                } => {
+                    if (&pilots).join().any(|pilot| pilot.url == request.url) {
+                        println!("duplicate pilot registration rejected: {}", request.url);
+                        let _ = reply.send(Err(Status::already_exists(format!(
+                            "a pilot is already registered at {}",
+                            request.url
+                        ))));
                         continue;
                     }
+
                     let new_entity = entities.create();
-                    match pilots.insert(new_entity, Pilot::new(&pilot_url)) {
-                        Err(_) => println!("oops! Trouble creating pilot"),
-                        Ok(_) => match fire_timers.insert(new_entity, FireTimer(0.0)) {
-                            Err(_) => println!("oops! Trouble creating fire timer"),
-                            Ok(_) => println!("new pilot"),
-                        },
+                    let lives = GameMode::from_settings(&settings).lives(&settings);
+                    let mut pilot = Pilot::new(&request.url, lives);
+                    if !request.name.is_empty() {
+                        pilot.name = request.name.clone();
                     }
+                    pilot.team = request.team.clone();
+                    pilot.color = request.color;
+                    pilot.can_reconnect = connection.is_none();
+                    pilot.connection = Some(
+                        connection
+                            .unwrap_or_else(|| PilotConnection::connect(&runtime, &request.url)),
+                    );
+                    let description = if pilot.name == request.url {
+                        request.url.clone()
+                    } else {
+                        format!("{} ({})", pilot.display_name(), request.url)
+                    };
+
+                    let created = pilots
+                        .insert(new_entity, pilot)
+                        .map_err(|_| "oops! Trouble creating pilot")
+                        .and_then(|_| {
+                            fire_timers
+                                .insert(new_entity, FireTimer(0.0))
+                                .map_err(|_| "oops! Trouble creating fire timer")
+                        })
+                        .and_then(|_| {
+                            stats
+                                .insert(new_entity, Stats::default())
+                                .map_err(|_| "oops! Trouble creating stats")
+                        });
+
+                    let response = match created {
+                        Err(e) => {
+                            println!("{}", e);
+                            let _ = entities.delete(new_entity);
+                            Err(Status::internal(e))
+                        }
+                        Ok(_) => {
+                            println!("new pilot: {}", description);
+                            Ok(RegistrationResponse {
+                                pilot_id: entity_id(new_entity),
+                                settings: Some(game_settings(&settings)),
+                            })
+                        }
+                    };
+                    let _ = reply.send(response);
+                }
+                RegistrarMessage::Unregister { request, reply } => {
+                    let leaving = (&entities, &pilots, &stats)
+                        .join()
+                        .find(|(entity, pilot, _)| is_unregistering(&request, *entity, pilot))
+                        .map(|(entity, pilot, stats)| {
+                            (entity, pilot.url.clone(), stats.to_rpc(pilot))
+                        });
+
+                    let response = match leaving {
+                        None if request.pilot_id != 0 => Err(Status::not_found(format!(
+                            "no pilot is registered with id {}",
+                            request.pilot_id
+                        ))),
+                        None => Err(Status::not_found(format!(
+                            "no pilot is registered at {}",
+                            request.url
+                        ))),
+                        Some((entity, url, final_stats)) => {
+                            println!("pilot left: {}", url);
+                            // Hang up now; the entity lingers until the end of the tick.
+                            if let Some(pilot) = pilots.get_mut(entity) {
+                                pilot.connection = None;
+                            }
+                            remove_pilot(
+                                entity,
+                                final_stats.clone(),
+                                &bullets,
+                                &entities,
+                                &mut departed,
+                            );
+                            Ok(UnregistrationResponse {
+                                stats: Some(final_stats),
+                            })
+                        }
+                    };
+                    let _ = reply.send(response);
                 }
             }
         }
+
+        // Pilots which have left stay in the rankings with their final stats
+        let snapshot = departed
+            .0
+            .iter()
+            .cloned()
+            .chain(
+                (&entities, &pilots, &stats)
+                    .join()
+                    .filter(|(entity, _, _)| entities.is_alive(*entity))
+                    .map(|(_, pilot, stats)| stats.to_rpc(pilot)),
+            )
+            .collect();
+        *self.stats.lock().unwrap() = snapshot;
+    }
+}
+
+/// Whether `request` is asking to unregister `pilot`. Pilots are found by id if the request has
+/// one, since pilots which joined over a stream don't know the url we filed them under.
+fn is_unregistering(request: &UnregistrationRequest, entity: Entity, pilot: &Pilot) -> bool {
+    if request.pilot_id != 0 {
+        entity_id(entity) == request.pilot_id
+    } else {
+        pilot.url == request.url
+    }
+}
+
+/// The settings we tell pilots about when they register.
+fn game_settings(settings: &Settings) -> rpc::GameSettings {
+    rpc::GameSettings {
+        field: Some(rpc::Field {
+            width: settings.screen_width as i32,
+            height: settings.screen_height as i32,
+        }),
+        tick_rate: settings.tick_rate,
+        ship_length: settings.ship_length,
+        ship_width: settings.ship_width,
+        ship_mass: settings.ship_mass,
+        ship_thrust: settings.ship_thrust,
+        ship_rotational_speed: settings.ship_rotational_speed,
+        cannon: Some(rpc::Cannon {
+            bullet_speed: settings.bullet_speed,
+            rate_of_fire: settings.rate_of_fire,
+            weapon: rpc::Weapon::Gun as i32,
+        }),
+        lives: GameMode::from_settings(settings).lives(settings),
+        ship_reverse_thrust: settings.ship_reverse_thrust,
+        ship_strafe_thrust: settings.ship_strafe_thrust,
+    }
+}
+
+/// Turn away pilots which speak a different version of the protocol.
+fn check_protocol_version(request: &RegistrationRequest) -> Result<(), Status> {
+    if request.protocol_version == PROTOCOL_VERSION {
+        Ok(())
+    } else {
+        Err(Status::failed_precondition(format!(
+            "pilot uses protocol version {} but the game uses version {}",
+            request.protocol_version, PROTOCOL_VERSION
+        )))
     }
 }
 
 // Listen for registrations on a URL, publishing them to a channel.
 async fn listen(
     addr: SocketAddr,
-    tx: Sender<String>,
+    tx: Sender<RegistrarMessage>,
+    stats: Arc<Mutex<Vec<PilotStats>>>,
     shutdown_rx: oneshot::Receiver<()>,
 ) -> Result<(), tonic::transport::Error> {
-    let registrar = Registrar { tx: Mutex::new(tx) };
+    let registrar = Registrar {
+        tx: Mutex::new(tx),
+        stats,
+        connections: AtomicU64::new(0),
+    };
     let svc = PilotRegistrarServer::new(registrar);
     println!("Listening for pilot registration on {:?}", addr);
     let shutdown = async {
@@ -100,23 +305,315 @@ async fn listen(
 /// Stores new registrations from pilots.
 /// This is the implementation of the grpc PilotRegistrar.
 struct Registrar {
-    tx: Mutex<Sender<String>>,
+    tx: Mutex<Sender<RegistrarMessage>>,
+    stats: Arc<Mutex<Vec<PilotStats>>>,
+    /// Number of connections pilots have opened to us.
+    connections: AtomicU64,
+}
+
+impl Registrar {
+    /// Pass a registration on to the game and wait for its decision.
+    async fn submit(
+        &self,
+        request: RegistrationRequest,
+        connection: Option<PilotConnection>,
+    ) -> Result<RegistrationResponse, Status> {
+        if let Err(status) = check_protocol_version(&request) {
+            println!(
+                "pilot registration rejected: {}: {}",
+                request.url,
+                status.message()
+            );
+            return Err(status);
+        }
+
+        // The game decides whether to accept the pilot the next time it runs the
+        // registration system.
+        let (reply, response) = oneshot::channel();
+        {
+            let send = self.tx.lock().unwrap();
+            send.send(RegistrarMessage::Register {
+                request,
+                connection,
+                reply,
+            })
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        }
+
+        response
+            .await
+            .unwrap_or_else(|_| Err(Status::unavailable("the game is shutting down")))
+    }
 }
 
 #[tonic::async_trait]
 impl PilotRegistrar for Registrar {
+    type JoinStream = Pin<Box<dyn Stream<Item = Result<GameMessage, Status>> + Send>>;
+
     async fn register(
         &self,
         request: Request<RegistrationRequest>,
     ) -> Result<Response<RegistrationResponse>, Status> {
-        let registration = request.get_ref();
+        self.submit(request.into_inner(), None)
+            .await
+            .map(Response::new)
+    }
+
+    async fn join(
+        &self,
+        request: Request<Streaming<PilotMessage>>,
+    ) -> Result<Response<Self::JoinStream>, Status> {
+        let remote_addr = request.remote_addr();
+        let mut inbound = request.into_inner();
+
+        let mut registration = match inbound.message().await? {
+            Some(PilotMessage {
+                message: Some(pilot_message::Message::Register(registration)),
+            }) => registration,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "the first message on a connection must be a registration",
+                ))
+            }
+        };
+
+        // We never call these pilots, so they're known by where they connected from.
+        let connection_number = self.connections.fetch_add(1, Ordering::Relaxed);
+        registration.url = match remote_addr {
+            Some(addr) => format!("connected://{}", addr),
+            None => format!("connected://{}", connection_number),
+        };
+
+        let (connection, states) = PilotConnection::accept(inbound);
+        let registered = self.submit(registration, Some(connection)).await?;
+
+        let registered = GameMessage {
+            message: Some(game_message::Message::Registered(registered)),
+        };
+        let states = states.filter_map(|state| {
+            state.map(|state| {
+                Ok(GameMessage {
+                    message: Some(game_message::Message::State(Box::new(state))),
+                })
+            })
+        });
+        Ok(Response::new(Box::pin(
+            tokio_stream::once(Ok(registered)).chain(states),
+        )))
+    }
+
+    async fn unregister(
+        &self,
+        request: Request<UnregistrationRequest>,
+    ) -> Result<Response<UnregistrationResponse>, Status> {
+        let request = request.into_inner();
 
+        let (reply, response) = oneshot::channel();
         {
             let send = self.tx.lock().unwrap();
-            send.send(registration.url.clone())
-                .or_else(|e| Err(Status::new(Code::Internal, e.to_string())))?;
+            send.send(RegistrarMessage::Unregister { request, reply })
+                .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
         }
 
-        Ok(Response::new(RegistrationResponse {}))
+        response
+            .await
+            .unwrap_or_else(|_| Err(Status::unavailable("the game is shutting down")))
+            .map(Response::new)
+    }
+
+    async fn get_stats(
+        &self,
+        _request: Request<StatsRequest>,
+    ) -> Result<Response<StatsResponse>, Status> {
+        let pilots = self.stats.lock().unwrap().clone();
+        Ok(Response::new(StatsResponse { pilots }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Weapon;
+    use specs::{Builder, RunNow, WorldExt};
+
+    fn make_world() -> World {
+        let mut world = World::new();
+        world.register::<Pilot>();
+        world.register::<FireTimer>();
+        world.register::<Stats>();
+        world.register::<Bullet>();
+        world.insert(DepartedPilots::default());
+        world.insert(Settings::load().expect("settings"));
+        world.insert(tokio::runtime::Runtime::new().unwrap());
+        world
+    }
+
+    fn request(url: &str, name: &str) -> RegistrationRequest {
+        RegistrationRequest {
+            url: url.to_string(),
+            name: name.to_string(),
+            team: "red".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            color: None,
+        }
+    }
+
+    fn register(
+        system: &mut PilotRegistrationSystem,
+        world: &World,
+        request: RegistrationRequest,
+    ) -> Result<RegistrationResponse, Status> {
+        let (reply, mut response) = oneshot::channel();
+        system
+            .tx
+            .send(RegistrarMessage::Register {
+                request,
+                connection: None,
+                reply,
+            })
+            .unwrap();
+        system.run_now(world);
+        response.try_recv().unwrap()
+    }
+
+    #[test]
+    fn registration_returns_pilot_id_and_settings() {
+        let world = make_world();
+        let mut system = PilotRegistrationSystem::new();
+
+        let response = register(&mut system, &world, request("http://[::1]:1", "ace")).unwrap();
+
+        let entities = world.entities();
+        let pilots = world.read_storage::<Pilot>();
+        let (entity, pilot) = (&entities, &pilots).join().next().unwrap();
+        assert_eq!(response.pilot_id, entity_id(entity));
+        assert_eq!(pilot.display_name(), "ace [red]");
+
+        let settings = response.settings.unwrap();
+        assert_eq!(
+            settings.tick_rate,
+            world.read_resource::<Settings>().tick_rate
+        );
+    }
+
+    fn unregister(
+        system: &mut PilotRegistrationSystem,
+        world: &World,
+        url: &str,
+    ) -> Result<UnregistrationResponse, Status> {
+        unregister_with(
+            system,
+            world,
+            UnregistrationRequest {
+                url: url.to_string(),
+                pilot_id: 0,
+            },
+        )
+    }
+
+    fn unregister_with(
+        system: &mut PilotRegistrationSystem,
+        world: &World,
+        request: UnregistrationRequest,
+    ) -> Result<UnregistrationResponse, Status> {
+        let (reply, mut response) = oneshot::channel();
+        system
+            .tx
+            .send(RegistrarMessage::Unregister { request, reply })
+            .unwrap();
+        system.run_now(world);
+        response.try_recv().unwrap()
+    }
+
+    #[test]
+    fn unregistering_removes_pilot_and_bullets() {
+        let mut world = make_world();
+        let mut system = PilotRegistrationSystem::new();
+
+        register(&mut system, &world, request("http://[::1]:1", "ace")).unwrap();
+        register(&mut system, &world, request("http://[::1]:2", "bob")).unwrap();
+        let find = |world: &World, url: &str| {
+            (&world.entities(), &world.read_storage::<Pilot>())
+                .join()
+                .find(|(_, pilot)| pilot.url == url)
+                .unwrap()
+                .0
+        };
+        let ace = find(&world, "http://[::1]:1");
+        let bob = find(&world, "http://[::1]:2");
+        world.write_storage::<Stats>().get_mut(ace).unwrap().score = 120;
+        let ace_bullet = world
+            .create_entity()
+            .with(Bullet::new(ace, Weapon::Gun))
+            .build();
+        let bob_bullet = world
+            .create_entity()
+            .with(Bullet::new(bob, Weapon::Gun))
+            .build();
+
+        let response = unregister(&mut system, &world, "http://[::1]:1").unwrap();
+        world.maintain();
+
+        assert_eq!(response.stats.unwrap().score, 120);
+        assert!(!world.is_alive(ace));
+        assert!(!world.is_alive(ace_bullet));
+        assert!(world.is_alive(bob));
+        assert!(world.is_alive(bob_bullet));
+
+        let departed = world.read_resource::<DepartedPilots>();
+        assert_eq!(departed.0.len(), 1);
+        assert_eq!(departed.0[0].name, "ace");
+    }
+
+    #[test]
+    fn unregistering_by_pilot_id() {
+        let mut world = make_world();
+        let mut system = PilotRegistrationSystem::new();
+
+        // Connected pilots never learn the url they're filed under.
+        let registered = register(&mut system, &world, request("connected://1", "ace")).unwrap();
+        let request = UnregistrationRequest {
+            url: String::new(),
+            pilot_id: registered.pilot_id,
+        };
+        unregister_with(&mut system, &world, request.clone()).unwrap();
+        world.maintain();
+        assert_eq!(world.read_storage::<Pilot>().count(), 0);
+
+        let status = unregister_with(&mut system, &world, request).unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[test]
+    fn unregistering_unknown_pilot_fails() {
+        let world = make_world();
+        let mut system = PilotRegistrationSystem::new();
+
+        let status = unregister(&mut system, &world, "http://[::1]:1").unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[test]
+    fn duplicate_registrations_are_rejected() {
+        let world = make_world();
+        let mut system = PilotRegistrationSystem::new();
+
+        register(&mut system, &world, request("http://[::1]:1", "ace")).unwrap();
+        let status = register(&mut system, &world, request("http://[::1]:1", "ace")).unwrap_err();
+
+        assert_eq!(status.code(), Code::AlreadyExists);
+        assert_eq!(world.read_storage::<Pilot>().count(), 1);
+    }
+
+    #[test]
+    fn mismatched_protocol_versions_are_rejected() {
+        let mut old = request("http://[::1]:1", "ace");
+        old.protocol_version = PROTOCOL_VERSION - 1;
+
+        assert!(check_protocol_version(&request("http://[::1]:1", "ace")).is_ok());
+        assert_eq!(
+            check_protocol_version(&old).unwrap_err().code(),
+            Code::FailedPrecondition
+        );
     }
 }
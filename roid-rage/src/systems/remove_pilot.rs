@@ -0,0 +1,34 @@
+use crate::components::{Bullet, DepartedPilots};
+use roid_rage_grpc::roid_rage as rpc;
+use specs::storage::MaskedStorage;
+use specs::{Entities, Entity, Join, Storage};
+use std::ops::Deref;
+
+/// Take a pilot out of the game, along with its ship and any bullets it has in flight.
+///
+/// The ship lives on the pilot's entity, so deleting that removes it, and
+/// `CleanupCollisionsSystem` takes the deleted entities out of the collision world. The
+/// pilot's final statistics are kept in `DepartedPilots`.
+pub fn remove_pilot<D>(
+    entity: Entity,
+    final_stats: rpc::PilotStats,
+    bullets: &Storage<Bullet, D>,
+    entities: &Entities,
+    departed: &mut DepartedPilots,
+) where
+    D: Deref<Target = MaskedStorage<Bullet>>,
+{
+    for (bullet_entity, bullet) in (entities, bullets).join() {
+        if bullet.owner == entity {
+            if let Err(err) = entities.delete(bullet_entity) {
+                println!("Failed to remove departing pilot's bullet: {:?}", err);
+            }
+        }
+    }
+
+    if let Err(err) = entities.delete(entity) {
+        println!("Failed to remove pilot entity: {:?}", err);
+    }
+
+    departed.0.push(final_stats);
+}
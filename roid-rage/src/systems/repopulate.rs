@@ -1,10 +1,15 @@
-use crate::components::{make_roid, Roid};
+use crate::components::{make_roid, GameProgress, GameRng, Roid};
 use crate::core::util::random_bearing;
+use crate::game_mode::GameMode;
 use crate::settings::Settings;
 use ncollide2d::world::CollisionWorld;
-use specs::{Entities, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect};
+use rand::Rng;
+use specs::{Entities, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write, WriteExpect};
 
 /// Repopulate the fields with roids if there are none.
+///
+/// Each time the field is emptied the next wave arrives, which the game mode may make bigger
+/// or faster than the last.
 pub struct RepopulateSystem;
 
 impl<'s> System<'s> for RepopulateSystem {
@@ -13,25 +18,32 @@ impl<'s> System<'s> for RepopulateSystem {
         Entities<'s>,
         WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
         ReadExpect<'s, Settings>,
+        WriteExpect<'s, GameRng>,
+        Write<'s, GameProgress>,
         Read<'s, LazyUpdate>,
     );
 
-    fn run(&mut self, (roids, entities, mut collision_world, settings, lazy): Self::SystemData) {
+    fn run(
+        &mut self,
+        (roids, entities, mut collision_world, settings, mut rng, mut progress, lazy): Self::SystemData,
+    ) {
         if !roids.is_empty() {
             return;
         }
 
-        use rand::Rng;
-        let mut rng = rand::rng();
-        for _ in 0..settings.initial_roid_count {
+        progress.wave += 1;
+        let wave = GameMode::from_settings(&settings).wave(progress.wave, &settings);
+
+        let rng = &mut rng.0;
+        for _ in 0..wave.roid_count {
             let x = rng.random::<f32>() * (settings.screen_width + settings.maximum_roid_radius);
             let y = rng.random::<f32>() * (settings.screen_height + settings.maximum_roid_radius);
             let speed = rng.random::<f32>()
                 * (settings.max_initial_roid_speed - settings.min_initial_roid_speed)
                 + settings.min_initial_roid_speed;
-            let course = random_bearing();
-            let radius =
-                rng.random::<f32>() * 5.0 + (settings.maximum_roid_radius - 5.0);
+            let speed = speed * wave.speed_factor;
+            let course = random_bearing(rng);
+            let radius = rng.random::<f32>() * 5.0 + (settings.maximum_roid_radius - 5.0);
             let angular_velocity = rng.random::<f32>() * 0.5;
             let new_entity = entities.create();
             make_roid(
@@ -46,6 +58,7 @@ impl<'s> System<'s> for RepopulateSystem {
                 angular_velocity,
                 radius,
                 settings.roid_bumpiness,
+                rng,
                 &mut collision_world,
             );
         }
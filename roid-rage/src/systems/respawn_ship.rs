@@ -1,7 +1,14 @@
 /// System responsible for creating ships for pilots with no ships.
-use crate::components::{make_ship, Cannon, Pilot, Ship};
+use crate::components::{
+    make_ship, Health, Invulnerable, Pilot, Position, RespawnTimer, Ship, ShipResources, TimeDelta,
+};
+use crate::core::field::Field;
 use crate::settings::Settings;
-use specs::{Builder, Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System};
+use glam::Vec2;
+use ncollide2d::world::CollisionWorld;
+use specs::{
+    Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
+};
 
 /// Respawn the ship if needed
 pub struct RespawnShipSystem;
@@ -10,39 +17,216 @@ impl<'s> System<'s> for RespawnShipSystem {
     type SystemData = (
         ReadStorage<'s, Pilot>,
         ReadStorage<'s, Ship>,
+        ReadStorage<'s, Position>,
+        WriteStorage<'s, RespawnTimer>,
         Entities<'s>,
         ReadExpect<'s, Settings>,
+        ReadExpect<'s, Field<f32>>,
+        Read<'s, TimeDelta>,
+        WriteExpect<'s, CollisionWorld<f32, specs::world::Index>>,
         Read<'s, LazyUpdate>,
     );
 
-    fn run(&mut self, (pilots, ships, entities, settings, lazy): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            pilots,
+            ships,
+            positions,
+            mut respawn_timers,
+            entities,
+            settings,
+            field,
+            time_delta,
+            mut collision_world,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        // Ships are created lazily, so keep track of where this tick's new ships will be as well
+        // as where the existing ones are.
+        let mut occupied: Vec<Vec2> = (&ships, &positions).join().map(|(_, p)| p.0).collect();
+
         // Find all pilots without a ship.
-        for (_pilot, entity, ()) in (&pilots, &entities, !&ships).join() {
-            let position_x = settings.screen_width / 2.0;
-            let position_y = settings.screen_height / 2.0;
+        for (pilot, entity, ()) in (&pilots, &entities, !&ships).join() {
+            // Pilots who have run out of lives are out of the game.
+            if pilot.lives == 0 {
+                continue;
+            }
+
+            // Pilots whose ship was destroyed wait out their respawn timer first.
+            if let Some(timer) = respawn_timers.get_mut(entity) {
+                timer.0 -= time_delta.0.as_secs_f32();
+                if timer.0 > 0.0 {
+                    continue;
+                }
+            }
+            respawn_timers.remove(entity);
+
+            let position = spawn_point(&field, &occupied);
+            occupied.push(position);
             let heading = 0.0;
 
             // Arrange for a ship to be created.
             make_ship(
                 specs::world::LazyBuilder {
-                    entity: entity,
-                    lazy: &*lazy,
+                    entity,
+                    lazy: &lazy,
                 },
+                Ship::from_settings(&settings),
                 heading,
-                settings.ship_length,
-                settings.ship_width,
-                settings.ship_mass,
-                settings.ship_thrust,
-                settings.ship_rotational_speed,
-                Cannon {
-                    bullet_speed: settings.bullet_speed,
-                    rate_of_fire: settings.rate_of_fire,
-                },
-                position_x,
-                position_y,
-                glam::Vec2::ZERO,
-            )
+                position.x,
+                position.y,
+                Vec2::ZERO,
+                settings.pvp,
+                &mut collision_world,
+            );
+            lazy.insert(entity, ShipResources::from_settings(&settings));
+            lazy.insert(entity, Health::from_settings(&settings));
+
+            // New ships get a moment to get their bearings before anything can hurt them.
+            if settings.respawn_invulnerability > 0.0 {
+                lazy.insert(entity, Invulnerable(settings.respawn_invulnerability));
+            }
+        }
+    }
+}
+
+/// Where to put a new ship, given where the other ships are.
+///
+/// The first ship goes in the middle of the field. After that we try the middle and a ring of
+/// points around it and pick whichever is furthest from its nearest ship.
+fn spawn_point(field: &Field<f32>, occupied: &[Vec2]) -> Vec2 {
+    let center = Vec2::new(field.width() / 2.0, field.height() / 2.0);
+    let radius = field.width().min(field.height()) / 3.0;
+    let ring = (0..8).map(|i| {
+        let bearing = i as f32 * std::f32::consts::FRAC_PI_4;
+        center + Vec2::new(bearing.cos(), bearing.sin()) * radius
+    });
+
+    let clearance = |point: &Vec2| {
+        occupied
+            .iter()
+            .map(|other| field.distance((point.x, point.y), (other.x, other.y)))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    std::iter::once(center)
+        .chain(ring)
+        .fold((center, f32::NEG_INFINITY), |best, point| {
+            let distance = clearance(&point);
+            if distance > best.1 {
+                (point, distance)
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{AngularVelocity, CollisionHandle, LinearVelocity, Rotation, Wrapping};
+    use specs::{Builder, RunNow, World, WorldExt};
+    use std::time::Duration;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Pilot>();
+        world.register::<Ship>();
+        world.register::<ShipResources>();
+        world.register::<Health>();
+        world.register::<Invulnerable>();
+        world.register::<RespawnTimer>();
+        world.register::<Position>();
+        world.register::<Rotation>();
+        world.register::<LinearVelocity>();
+        world.register::<AngularVelocity>();
+        world.register::<Wrapping>();
+        world.register::<CollisionHandle>();
+        world.insert(Settings::load().expect("settings"));
+        world.insert(Field::new(800.0_f32, 600.0_f32));
+        world.insert(TimeDelta(Duration::from_secs_f32(0.5)));
+        world.insert(CollisionWorld::<f32, specs::world::Index>::new(0.02f32));
+        world
+    }
+
+    #[test]
+    fn new_pilot_gets_ship_immediately() {
+        let mut world = world();
+        let pilot = world.create_entity().with(Pilot::new("pilot", 3)).build();
+
+        RespawnShipSystem.run_now(&world);
+        world.maintain();
+
+        assert!(world.read_storage::<Ship>().get(pilot).is_some());
+        assert!(world.read_storage::<Health>().get(pilot).is_some());
+        assert!(world.read_storage::<Invulnerable>().get(pilot).is_some());
+        let collision_world = world.read_resource::<CollisionWorld<f32, specs::world::Index>>();
+        assert_eq!(collision_world.collision_objects().count(), 1);
+    }
+
+    #[test]
+    fn waits_for_respawn_timer() {
+        let mut world = world();
+        let pilot = world
+            .create_entity()
+            .with(Pilot::new("pilot", 3))
+            .with(RespawnTimer(0.75))
             .build();
+
+        RespawnShipSystem.run_now(&world);
+        world.maintain();
+        assert!(world.read_storage::<Ship>().get(pilot).is_none());
+
+        RespawnShipSystem.run_now(&world);
+        world.maintain();
+        assert!(world.read_storage::<Ship>().get(pilot).is_some());
+        assert!(world.read_storage::<RespawnTimer>().get(pilot).is_none());
+    }
+
+    #[test]
+    fn no_ship_without_lives() {
+        let mut world = world();
+        let pilot = world.create_entity().with(Pilot::new("pilot", 0)).build();
+
+        RespawnShipSystem.run_now(&world);
+        world.maintain();
+
+        assert!(world.read_storage::<Ship>().get(pilot).is_none());
+    }
+
+    #[test]
+    fn first_ship_spawns_in_the_middle() {
+        let field = Field::new(800.0_f32, 600.0_f32);
+        assert_eq!(spawn_point(&field, &[]), Vec2::new(400.0, 300.0));
+    }
+
+    #[test]
+    fn ships_spawning_together_are_spread_out() {
+        let mut world = world();
+        let pilots: Vec<_> = (0..3)
+            .map(|i| {
+                world
+                    .create_entity()
+                    .with(Pilot::new(&format!("pilot{}", i), 3))
+                    .build()
+            })
+            .collect();
+
+        RespawnShipSystem.run_now(&world);
+        world.maintain();
+
+        let field = world.read_resource::<Field<f32>>();
+        let positions = world.read_storage::<Position>();
+        let positions: Vec<Vec2> = pilots
+            .iter()
+            .map(|pilot| positions.get(*pilot).expect("ship").0)
+            .collect();
+        for (i, a) in positions.iter().enumerate() {
+            for b in &positions[i + 1..] {
+                assert!(field.distance((a.x, a.y), (b.x, b.y)) > 100.0);
+            }
         }
     }
 }
@@ -0,0 +1,180 @@
+use crate::components::{Bullet, LinearVelocity, Missile, Pilot, Position, Roid, Ship, TimeDelta};
+use crate::core::field::Field;
+use crate::settings::Settings;
+use glam::Vec2;
+use specs::{Entities, Join, Read, ReadExpect, ReadStorage, System, WriteStorage};
+
+pub struct SteerMissilesSystem;
+
+/// Turn homing missiles towards their nearest target, and remove those which have run out of
+/// fuel.
+///
+/// Missiles go after roids and, in PvP games, the ships they could hit. Like everything else they
+/// can cross the edges of the field, so targets are found and chased the shortest way round.
+impl<'s> System<'s> for SteerMissilesSystem {
+    type SystemData = (
+        WriteStorage<'s, Missile>,
+        WriteStorage<'s, LinearVelocity>,
+        ReadStorage<'s, Position>,
+        ReadStorage<'s, Bullet>,
+        ReadStorage<'s, Roid>,
+        ReadStorage<'s, Ship>,
+        ReadStorage<'s, Pilot>,
+        Entities<'s>,
+        Read<'s, TimeDelta>,
+        ReadExpect<'s, Field<f32>>,
+        ReadExpect<'s, Settings>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut missiles,
+            mut linear_velocities,
+            positions,
+            bullets,
+            roids,
+            ships,
+            pilots,
+            entities,
+            time_delta,
+            field,
+            settings,
+        ): Self::SystemData,
+    ) {
+        let dt = time_delta.0.as_secs_f32();
+
+        let roid_positions: Vec<Vec2> = (&roids, &positions).join().map(|(_, p)| p.0).collect();
+        let ship_positions: Vec<_> = (&ships, &positions, &entities)
+            .join()
+            .map(|(_, p, entity)| (entity, p.0))
+            .collect();
+
+        for (missile, bullet, position, linear_velocity, entity) in (
+            &mut missiles,
+            &bullets,
+            &positions,
+            &mut linear_velocities,
+            &entities,
+        )
+            .join()
+        {
+            missile.time_left -= dt;
+            if missile.time_left <= 0.0 {
+                if let Err(e) = entities.delete(entity) {
+                    println!("Error deleting missile: {}", e);
+                }
+                continue;
+            }
+
+            let can_hit = |ship: &specs::Entity| {
+                settings.pvp
+                    && *ship != bullet.owner
+                    && match (pilots.get(bullet.owner), pilots.get(*ship)) {
+                        (Some(owner), Some(target)) => {
+                            settings.friendly_fire || !owner.is_teammate(target)
+                        }
+                        _ => true,
+                    }
+            };
+            let to_target = roid_positions
+                .iter()
+                .copied()
+                .chain(
+                    ship_positions
+                        .iter()
+                        .filter(|(ship, _)| can_hit(ship))
+                        .map(|(_, p)| *p),
+                )
+                .map(|target| {
+                    let (dx, dy) = field
+                        .shortest_displacement((position.0.x, position.0.y), (target.x, target.y));
+                    Vec2::new(dx, dy)
+                })
+                .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+
+            if let Some(to_target) = to_target {
+                linear_velocity.0 = steer(
+                    linear_velocity.0,
+                    to_target,
+                    settings.missile_turn_rate * dt,
+                );
+            }
+        }
+    }
+}
+
+/// Turn `velocity` by at most `max_turn` radians towards `direction`, keeping its speed.
+fn steer(velocity: Vec2, direction: Vec2, max_turn: f32) -> Vec2 {
+    if velocity == Vec2::ZERO || direction == Vec2::ZERO {
+        return velocity;
+    }
+    let turn = velocity.angle_to(direction).clamp(-max_turn, max_turn);
+    Vec2::from_angle(turn).rotate(velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Weapon;
+    use specs::{Builder, RunNow, World, WorldExt};
+    use std::time::Duration;
+
+    #[test]
+    fn steering_is_limited_by_turn_rate() {
+        let velocity = Vec2::new(10.0, 0.0);
+
+        let turned = steer(velocity, Vec2::new(0.0, 1.0), 0.5);
+        assert!((turned.length() - 10.0).abs() < 0.001);
+        assert!((Vec2::X.angle_to(turned) - 0.5).abs() < 0.001);
+
+        let turned = steer(velocity, Vec2::new(1.0, -0.1), 0.5);
+        assert!((turned.normalize() - Vec2::new(1.0, -0.1).normalize()).length() < 0.001);
+    }
+
+    #[test]
+    fn missiles_chase_targets_across_the_edges() {
+        let mut world = World::new();
+        world.register::<Missile>();
+        world.register::<LinearVelocity>();
+        world.register::<Position>();
+        world.register::<Bullet>();
+        world.register::<Roid>();
+        world.register::<Ship>();
+        world.register::<Pilot>();
+        world.insert(TimeDelta(Duration::from_secs_f32(0.1)));
+        world.insert(Field::<f32>::new(800.0, 600.0));
+        world.insert(Settings::load().expect("settings"));
+
+        // Just across the right edge, and much further away on the field itself
+        for x in [5.0, 700.0] {
+            world
+                .create_entity()
+                .with(Roid {
+                    radius: 10.0,
+                    points: Vec::new(),
+                })
+                .with(Position(Vec2::new(x, 300.0)))
+                .build();
+        }
+
+        let owner = world.create_entity().build();
+        let missile = world
+            .create_entity()
+            .with(Missile { time_left: 1.0 })
+            .with(Bullet::new(owner, Weapon::Homing))
+            .with(Position(Vec2::new(790.0, 300.0)))
+            .with(LinearVelocity(Vec2::new(0.0, -10.0)))
+            .build();
+
+        SteerMissilesSystem.run_now(&world);
+
+        // It turns right, towards the roid across the edge
+        let velocity = world
+            .read_storage::<LinearVelocity>()
+            .get(missile)
+            .unwrap()
+            .0;
+        assert!(velocity.x > 0.0);
+    }
+}
@@ -53,7 +53,7 @@ mod tests {
         let positions = world.read_storage::<Position>();
         let wrapping = world.read_storage::<Wrapping>();
         for (position, _wrap) in (&positions, &wrapping).join() {
-            assert_eq!(position.0, glam::Vec2::new(0.0, 5.0));
+            assert_eq!(position.0, glam::Vec2::new(1.0, 5.0));
         }
         for (position, _entity) in (&positions, !&wrapping).join() {
             assert_eq!(position.0, glam::Vec2::new(11.0, 5.0));
@@ -1,27 +1,45 @@
-// mod targeting;
-
-extern crate nalgebra;
-
-// use crate::targeting::find_target;
 use tonic::{Request, Response, Status};
 
+use pilot_lib::steering::turn_to;
+use pilot_lib::targeting::{find_target, SoonestImpact};
 use roid_rage_grpc::roid_rage::pilot_server::Pilot;
-
 use roid_rage_grpc::roid_rage::{Command, GameState, Rotation};
+use sted::to_vector;
+
+/// How far off target, in radians, we're willing to shoot. This is about the distance the
+/// ship turns in one tick.
+const AIM_TOLERANCE: f32 = 0.1;
 
 #[derive(Default)]
 struct SimplePilot {}
 
 #[tonic::async_trait]
 impl Pilot for SimplePilot {
-    async fn get_command(&self, _request: Request<GameState>) -> Result<Response<Command>, Status> {
-        let command = Command {
-            fire: false,
-            rotation: Rotation::None as i32,
-            thrusters: false,
-        };
-        Ok(Response::new(command))
+    async fn get_command(&self, request: Request<GameState>) -> Result<Response<Command>, Status> {
+        Ok(Response::new(aim_and_fire(request.get_ref())))
+    }
+}
+
+/// Turn towards the best intercept and fire once we're pointing at it.
+fn aim_and_fire(game_state: &GameState) -> Command {
+    let mut command = Command::null();
+
+    let ship = match &game_state.ship {
+        Some(ship) => ship,
+        None => return command,
+    };
+
+    if let Some(intercept) = find_target(game_state, &SoonestImpact) {
+        let target = to_vector(intercept.bearing);
+        if to_vector(ship.heading).angle_to(target).abs() < AIM_TOLERANCE {
+            command.rotation = Rotation::None as i32;
+            command.fire = game_state.time_to_fire <= 0.0;
+        } else {
+            command.rotation = turn_to(ship.heading, target) as i32;
+        }
     }
+
+    command
 }
 
 #[tokio::main]
@@ -29,3 +47,57 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let pilot = SimplePilot::default();
     pilot_lib::pilot_base::pilot_main(pilot).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roid_rage_grpc::roid_rage as rpc;
+
+    fn game_state(heading: f32, time_to_fire: f32) -> GameState {
+        GameState {
+            field: Some(rpc::Field {
+                width: 800,
+                height: 600,
+            }),
+            firing_position: Some(rpc::Position { x: 400.0, y: 300.0 }),
+            time_to_fire,
+            roids: vec![rpc::Roid {
+                radius: 10.0,
+                position: Some(rpc::Position { x: 500.0, y: 300.0 }),
+                velocity: Some(rpc::Velocity { x: 0.0, y: 0.0 }),
+                ..Default::default()
+            }],
+            ship: Some(rpc::Ship {
+                heading,
+                position: Some(rpc::Position { x: 400.0, y: 300.0 }),
+                cannon: Some(rpc::Cannon {
+                    bullet_speed: 1000.0,
+                    rate_of_fire: 0.5,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fires_when_aligned_and_ready() {
+        let command = aim_and_fire(&game_state(0.0, 0.0));
+        assert!(command.fire);
+        assert_eq!(command.rotation, Rotation::None as i32);
+    }
+
+    #[test]
+    fn holds_fire_while_reloading() {
+        let command = aim_and_fire(&game_state(0.0, 0.2));
+        assert!(!command.fire);
+    }
+
+    #[test]
+    fn turns_towards_target() {
+        let command = aim_and_fire(&game_state(1.0, 0.0));
+        assert!(!command.fire);
+        assert_ne!(command.rotation, Rotation::None as i32);
+    }
+}
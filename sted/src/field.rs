@@ -0,0 +1,156 @@
+use num::{Float, FromPrimitive};
+
+pub struct Field<T> {
+    width: T,
+    height: T,
+}
+
+impl<T> Field<T>
+where
+    T: Float + FromPrimitive + Copy,
+{
+    pub fn new(width: T, height: T) -> Self {
+        Field { width, height }
+    }
+
+    pub fn width(&self) -> T {
+        self.width
+    }
+
+    pub fn height(&self) -> T {
+        self.height
+    }
+
+    pub fn contains(&self, x: T, y: T) -> bool {
+        let zero = T::zero();
+        x >= zero && x <= self.width && y >= zero && y <= self.height
+    }
+
+    /// Wrap a point back onto the field.
+    ///
+    /// The field is a torus, so a point which has gone some way past one edge comes back in the
+    /// same distance past the opposite edge. The result is always in `[0, width) x [0, height)`.
+    pub fn wrap(&self, x: T, y: T) -> (T, T) {
+        (
+            wrap_coordinate(x, self.width),
+            wrap_coordinate(y, self.height),
+        )
+    }
+
+    /// The shortest way to get from `from` to `to`, possibly across the edges of the field.
+    pub fn shortest_displacement(&self, from: (T, T), to: (T, T)) -> (T, T) {
+        (
+            shortest_delta(to.0 - from.0, self.width),
+            shortest_delta(to.1 - from.1, self.height),
+        )
+    }
+
+    /// The distance between two points, possibly across the edges of the field.
+    pub fn distance(&self, a: (T, T), b: (T, T)) -> T {
+        let (dx, dy) = self.shortest_displacement(a, b);
+        dx.hypot(dy)
+    }
+
+    /// A point and its images in the eight neighbouring copies of the field.
+    ///
+    /// Anything near an edge also appears just past the opposite edge, so checking against
+    /// all of these finds things across the edges as well as on the field itself. The point
+    /// itself comes first.
+    pub fn images(&self, x: T, y: T) -> [(T, T); 9] {
+        let (w, h) = (self.width, self.height);
+        [
+            (x, y),
+            (x - w, y - h),
+            (x, y - h),
+            (x + w, y - h),
+            (x - w, y),
+            (x + w, y),
+            (x - w, y + h),
+            (x, y + h),
+            (x + w, y + h),
+        ]
+    }
+}
+
+fn wrap_coordinate<T: Float>(value: T, size: T) -> T {
+    let wrapped = value - (value / size).floor() * size;
+    // Rounding can leave tiny negative values just below `size`
+    if wrapped >= size {
+        T::zero()
+    } else {
+        wrapped
+    }
+}
+
+fn shortest_delta<T: Float + FromPrimitive>(delta: T, size: T) -> T {
+    delta - (delta / size).round() * size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Field;
+
+    #[test]
+    fn contains_includes_bounds() {
+        let field = Field::new(10.0_f32, 5.0_f32);
+        assert!(field.contains(0.0, 0.0));
+        assert!(field.contains(10.0, 5.0));
+        assert!(!field.contains(-0.1, 1.0));
+        assert!(!field.contains(1.0, 5.1));
+    }
+
+    #[test]
+    fn wrap_keeps_overshoot() {
+        let field = Field::new(10.0_f32, 5.0_f32);
+        assert_eq!(field.wrap(-1.0, 2.0), (9.0, 2.0));
+        assert_eq!(field.wrap(11.0, 2.0), (1.0, 2.0));
+        assert_eq!(field.wrap(3.0, -1.0), (3.0, 4.0));
+        assert_eq!(field.wrap(3.0, 6.0), (3.0, 1.0));
+        assert_eq!(field.wrap(25.0, -12.0), (5.0, 3.0));
+    }
+
+    #[test]
+    fn wrap_leaves_field_points_alone() {
+        let field = Field::new(10.0_f32, 5.0_f32);
+        assert_eq!(field.wrap(3.0, 2.0), (3.0, 2.0));
+        assert_eq!(field.wrap(0.0, 0.0), (0.0, 0.0));
+        assert_eq!(field.wrap(10.0, 5.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn shortest_displacement_crosses_edges() {
+        let field = Field::new(10.0_f32, 5.0_f32);
+        assert_eq!(
+            field.shortest_displacement((1.0, 1.0), (3.0, 2.0)),
+            (2.0, 1.0)
+        );
+        assert_eq!(
+            field.shortest_displacement((1.0, 1.0), (9.0, 4.0)),
+            (-2.0, -2.0)
+        );
+        assert_eq!(
+            field.shortest_displacement((9.0, 4.0), (1.0, 1.0)),
+            (2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn distance_crosses_edges() {
+        let field = Field::new(10.0_f32, 5.0_f32);
+        assert_eq!(field.distance((1.0, 1.0), (4.0, 1.0)), 3.0);
+        assert_eq!(field.distance((1.0, 1.0), (9.0, 1.0)), 2.0);
+        assert_eq!(field.distance((0.5, 0.5), (9.5, 4.5)), 2.0_f32.sqrt());
+    }
+
+    #[test]
+    fn images_surround_the_point() {
+        let field = Field::new(10.0_f32, 5.0_f32);
+        let images = field.images(1.0, 2.0);
+        assert_eq!(images[0], (1.0, 2.0));
+        for x in &[-9.0, 1.0, 11.0] {
+            for y in &[-3.0, 2.0, 7.0] {
+                assert!(images.contains(&(*x, *y)));
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
-mod velocity;
+pub mod collide;
 mod direction;
+pub mod field;
+mod velocity;
 
-pub use velocity::Velocity;
 pub use direction::to_vector;
+pub use field::Field;
+pub use velocity::Velocity;